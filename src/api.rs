@@ -2,8 +2,28 @@ use crate::common::{
     self, CerberusError, CerberusResult, Projection, Projections, SubscriptionSummary,
 };
 
+use percent_encoding::{utf8_percent_encode, AsciiSet, PercentEncode, CONTROLS};
 use serde::{Deserialize, Serialize};
 
+/// Characters that can't appear as-is in a URL path segment.
+const PATH_SEGMENT: &AsciiSet = &CONTROLS
+    .add(b' ')
+    .add(b'"')
+    .add(b'#')
+    .add(b'%')
+    .add(b'/')
+    .add(b'<')
+    .add(b'>')
+    .add(b'?')
+    .add(b'`')
+    .add(b'{')
+    .add(b'}');
+
+/// Escapes a name so it lands in a single path segment.
+fn segment(value: &str) -> PercentEncode<'_> {
+    utf8_percent_encode(value, PATH_SEGMENT)
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "camelCase")]
 pub struct SubscriptionDetail {
@@ -12,14 +32,83 @@ pub struct SubscriptionDetail {
     pub config: SubscriptionConfig,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionInfo {
+    pub event_stream_id: String,
+    pub group_name: String,
+    pub status: String,
+
+    #[serde(default)]
+    pub average_items_per_second: f64,
+
+    #[serde(default)]
+    pub total_items_processed: i64,
+
+    #[serde(default)]
+    pub last_processed_event_number: i64,
+
+    #[serde(default)]
+    pub last_known_event_number: i64,
+
+    #[serde(default)]
+    pub read_buffer_count: i64,
+
+    #[serde(default)]
+    pub live_buffer_count: i64,
+
+    #[serde(default)]
+    pub retry_buffer_count: i64,
+
+    #[serde(default)]
+    pub total_in_flight_messages: i64,
+
+    #[serde(default)]
+    pub connections: Vec<SubscriptionConnection>,
+
+    #[serde(default)]
+    pub config: Option<SubscriptionConfig>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SubscriptionConnection {
+    #[serde(default)]
+    pub from: String,
+
+    #[serde(default)]
+    pub username: String,
+
+    #[serde(default)]
+    pub connection_name: Option<String>,
+
+    #[serde(default)]
+    pub average_items_per_second: f64,
+
+    /// Unlike the subscription itself, connections report it as `totalItems`.
+    #[serde(default, rename = "totalItems")]
+    pub total_items_processed: i64,
+
+    #[serde(default)]
+    pub available_slots: i64,
+
+    #[serde(default)]
+    pub in_flight_messages: i64,
+
+    /// Latency percentiles, only tracked when the subscription has extra
+    /// statistics enabled.
+    #[serde(default)]
+    pub extra_statistics: Option<serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub enum NamedConsumerStrategy {
     RoundRobin,
     DispatchToSingle,
     Pinned,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct SubscriptionConfig {
     pub resolve_linktos: bool,
@@ -65,6 +154,54 @@ pub struct ProjectionConfig {
 
     #[serde(default)]
     pub emit_enabled: bool,
+
+    #[serde(default)]
+    pub track_emitted_streams: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserDetails {
+    pub login_name: String,
+    pub full_name: String,
+
+    #[serde(default)]
+    pub groups: Vec<String>,
+
+    #[serde(default)]
+    pub disabled: bool,
+
+    pub date_last_updated: Option<String>,
+}
+
+/// Every `/users` endpoint wraps its payload into a `data` property.
+#[derive(Deserialize)]
+struct UsersEnvelope<A> {
+    data: A,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserCreation<'a> {
+    pub login_name: &'a str,
+    pub full_name: &'a str,
+    pub groups: Vec<String>,
+    pub password: &'a str,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct UserUpdate<'a> {
+    pub full_name: &'a str,
+    pub groups: Vec<String>,
+}
+
+/// Which of the projection's companion streams get deleted along with it.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct DeleteProjectionOptions {
+    pub checkpoint_stream: bool,
+    pub state_stream: bool,
+    pub emitted_streams: bool,
 }
 
 pub struct Api<'a> {
@@ -258,7 +395,9 @@ impl<'a> Api<'a> {
     ) -> CerberusResult<Option<common::CroppedProjectionInfo>> {
         let req = self.client.get(&format!(
             "http://{}:{}/projection/{}",
-            self.host, self.port, projection_name
+            self.host,
+            self.port,
+            segment(projection_name)
         ));
 
         let resp = req.send().await.map_err(|e| {
@@ -284,6 +423,170 @@ impl<'a> Api<'a> {
         default_error_handler(resp).await
     }
 
+    pub async fn enable_projection(&self, projection_name: &str) -> CerberusResult<()> {
+        self.projection_command(projection_name, "enable").await
+    }
+
+    pub async fn disable_projection(&self, projection_name: &str) -> CerberusResult<()> {
+        self.projection_command(projection_name, "disable").await
+    }
+
+    pub async fn abort_projection(&self, projection_name: &str) -> CerberusResult<()> {
+        self.projection_command(projection_name, "abort").await
+    }
+
+    pub async fn reset_projection(&self, projection_name: &str) -> CerberusResult<()> {
+        self.projection_command(projection_name, "reset").await
+    }
+
+    async fn projection_command(&self, projection_name: &str, command: &str) -> CerberusResult<()> {
+        let req = self
+            .client
+            .post(&format!(
+                "http://{}:{}/projection/{}/command/{}",
+                self.host,
+                self.port,
+                segment(projection_name),
+                command
+            ))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/json;charset=UTF-8",
+            )
+            .body("{}");
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Projection [{}] doesn't exist.",
+                projection_name
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn projection_state(
+        &self,
+        projection_name: &str,
+        partition: Option<&str>,
+    ) -> CerberusResult<Option<serde_json::Value>> {
+        self.projection_document(projection_name, "state", partition)
+            .await
+    }
+
+    pub async fn projection_result(
+        &self,
+        projection_name: &str,
+        partition: Option<&str>,
+    ) -> CerberusResult<Option<serde_json::Value>> {
+        self.projection_document(projection_name, "result", partition)
+            .await
+    }
+
+    /// Fetches a projection state or result. The server answers with an empty
+    /// body when nothing has been computed yet.
+    async fn projection_document(
+        &self,
+        projection_name: &str,
+        document: &str,
+        partition: Option<&str>,
+    ) -> CerberusResult<Option<serde_json::Value>> {
+        let mut req = self.client.get(&format!(
+            "http://{}:{}/projection/{}/{}",
+            self.host,
+            self.port,
+            segment(projection_name),
+            document
+        ));
+
+        if let Some(partition) = partition {
+            req = req.query(&[("partition", partition)]);
+        }
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            let body = resp.text().await.map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to read projection {}: {}", document, e))
+            })?;
+
+            if body.trim().is_empty() {
+                return Ok(None);
+            }
+
+            let value = serde_json::from_str(body.as_str()).map_err(|e| {
+                CerberusError::dev_fault(format!(
+                    "Failed to deserialize projection {}: {}",
+                    document, e
+                ))
+            })?;
+
+            return Ok(Some(value));
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Projection [{}] doesn't exist.",
+                projection_name
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn delete_projection(
+        &self,
+        projection_name: &str,
+        opts: DeleteProjectionOptions,
+    ) -> CerberusResult<()> {
+        let yes_no = |value: bool| if value { "yes" } else { "no" };
+        let query = vec![
+            ("deleteCheckpointStream", yes_no(opts.checkpoint_stream)),
+            ("deleteStateStream", yes_no(opts.state_stream)),
+            ("deleteEmittedStreams", yes_no(opts.emitted_streams)),
+        ];
+
+        let req = self
+            .client
+            .delete(&format!(
+                "http://{}:{}/projection/{}",
+                self.host,
+                self.port,
+                segment(projection_name)
+            ))
+            .query(&query);
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Projection [{}] doesn't exist.",
+                projection_name
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
     pub async fn node_info(&self) -> CerberusResult<common::NodeInfo> {
         let req = self.client.get(&format!(
             "http://{}:{}/info?format=json",
@@ -349,7 +652,10 @@ impl<'a> Api<'a> {
 
         if resp.status().is_success() {
             return resp.json().await.map_err(|e| {
-                CerberusError::dev_fault(format!("Failed to deserialize SubscriptionSummary: {}", e))
+                CerberusError::dev_fault(format!(
+                    "Failed to deserialize SubscriptionSummary: {}",
+                    e
+                ))
             });
         }
 
@@ -389,8 +695,40 @@ impl<'a> Api<'a> {
             "http://{}:{}/subscriptions/{}/{}/info",
             self.host(),
             self.port(),
-            stream,
-            group_id
+            segment(stream),
+            segment(group_id)
+        );
+
+        let req = self.client.get(&url);
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return resp.json().await.map_err(|e| {
+                CerberusError::dev_fault(format!(
+                    "Failed to deserialize SubscriptionSummary: {}",
+                    e
+                ))
+            });
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn subscription_info(
+        &self,
+        stream: &str,
+        group_id: &str,
+    ) -> CerberusResult<SubscriptionInfo> {
+        let url = format!(
+            "http://{}:{}/subscriptions/{}/{}/info",
+            self.host(),
+            self.port(),
+            segment(stream),
+            segment(group_id)
         );
 
         let req = self.client.get(&url);
@@ -402,10 +740,17 @@ impl<'a> Api<'a> {
 
         if resp.status().is_success() {
             return resp.json().await.map_err(|e| {
-                CerberusError::dev_fault(format!("Failed to deserialize SubscriptionSummary: {}", e))
+                CerberusError::dev_fault(format!("Failed to deserialize SubscriptionInfo: {}", e))
             });
         }
 
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Persistent subscription targetting [{}] stream on group [{}] doesn't exist.",
+                stream, group_id
+            )));
+        }
+
         default_error_handler(resp).await
     }
 
@@ -436,8 +781,8 @@ impl<'a> Api<'a> {
             "http://{}:{}/subscriptions/{}/{}/info",
             self.host(),
             self.port(),
-            stream,
-            group_id
+            segment(stream),
+            segment(group_id)
         );
 
         let req = self.client.get(&url);
@@ -484,6 +829,42 @@ impl<'a> Api<'a> {
         default_error_handler(resp).await
     }
 
+    pub async fn projection_statistics(&self, projection_name: &str) -> CerberusResult<Projection> {
+        let req = self.client.get(&format!(
+            "http://{}:{}/projection/{}/statistics",
+            self.host,
+            self.port,
+            segment(projection_name)
+        ));
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            let result: Projections = resp.json().await.map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to deserialize Projections: {}", e))
+            })?;
+
+            return result.projections.into_iter().next().ok_or_else(|| {
+                CerberusError::dev_fault(format!(
+                    "Statistics of projection [{}] are missing from the server response",
+                    projection_name
+                ))
+            });
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Projection [{}] doesn't exist.",
+                projection_name
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
     pub async fn create_subscription(
         &self,
         stream: &str,
@@ -492,7 +873,10 @@ impl<'a> Api<'a> {
     ) -> CerberusResult<()> {
         let url = format!(
             "http://{}:{}/subscriptions/{}/{}",
-            self.host, self.port, stream, group
+            self.host,
+            self.port,
+            segment(stream),
+            segment(group)
         );
 
         let req = self
@@ -524,7 +908,10 @@ impl<'a> Api<'a> {
     ) -> CerberusResult<()> {
         let url = format!(
             "http://{}:{}/subscriptions/{}/{}",
-            self.host, self.port, stream, group
+            self.host,
+            self.port,
+            segment(stream),
+            segment(group)
         );
 
         let req = self
@@ -548,6 +935,36 @@ impl<'a> Api<'a> {
         default_error_handler(resp).await
     }
 
+    pub async fn delete_subscription(&self, stream: &str, group: &str) -> CerberusResult<()> {
+        let url = format!(
+            "http://{}:{}/subscriptions/{}/{}",
+            self.host,
+            self.port,
+            segment(stream),
+            segment(group)
+        );
+
+        let resp = self
+            .client
+            .delete(&url)
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err(CerberusError::user_fault(format!(
+                "Persistent subscription targetting [{}] stream on group [{}] doesn't exist.",
+                stream, group
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
     pub async fn projection_config(
         &self,
         projection_name: &str,
@@ -556,7 +973,7 @@ impl<'a> Api<'a> {
             "http://{}:{}/projection/{}/query",
             self.host(),
             self.port(),
-            projection_name
+            segment(projection_name)
         );
 
         let query = vec![("config", "yes")];
@@ -585,7 +1002,7 @@ impl<'a> Api<'a> {
             "http://{}:{}/projection/{}/query",
             self.host(),
             self.port(),
-            conf.name
+            segment(conf.name)
         );
 
         let emit_value = format!("{}", conf.emit);
@@ -617,4 +1034,245 @@ impl<'a> Api<'a> {
 
         default_error_handler(resp).await
     }
+
+    pub async fn users(&self) -> CerberusResult<Vec<UserDetails>> {
+        let req = self
+            .client
+            .get(&format!("http://{}:{}/users", self.host, self.port));
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            let envelope: UsersEnvelope<Vec<UserDetails>> = resp.json().await.map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to deserialize UserDetails list: {}", e))
+            })?;
+
+            return Ok(envelope.data);
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn user(&self, login: &str) -> CerberusResult<UserDetails> {
+        let user_opt = self.user_opt(login).await?;
+
+        match user_opt {
+            Some(user) => Ok(user),
+
+            None => Err(CerberusError::user_fault(format!(
+                "User [{}] doesn't exist.",
+                login
+            ))),
+        }
+    }
+
+    pub async fn user_opt(&self, login: &str) -> CerberusResult<Option<UserDetails>> {
+        let req = self.client.get(&format!(
+            "http://{}:{}/users/{}",
+            self.host,
+            self.port,
+            segment(login)
+        ));
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            let envelope: UsersEnvelope<UserDetails> = resp.json().await.map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to deserialize UserDetails: {}", e))
+            })?;
+
+            return Ok(Some(envelope.data));
+        }
+
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn create_user(&self, user: UserCreation<'_>) -> CerberusResult<()> {
+        let req = self
+            .client
+            .post(&format!("http://{}:{}/users", self.host, self.port))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/json;charset=UTF-8",
+            )
+            .body(serde_json::to_vec(&user).unwrap());
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        if resp.status() == reqwest::StatusCode::CONFLICT {
+            return Err(CerberusError::user_fault(format!(
+                "User [{}] already exists.",
+                user.login_name
+            )));
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn update_user(&self, login: &str, update: UserUpdate<'_>) -> CerberusResult<()> {
+        let req = self
+            .client
+            .put(&format!(
+                "http://{}:{}/users/{}",
+                self.host,
+                self.port,
+                segment(login)
+            ))
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/json;charset=UTF-8",
+            )
+            .body(serde_json::to_vec(&update).unwrap());
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn delete_user(&self, login: &str) -> CerberusResult<()> {
+        let req = self.client.delete(&format!(
+            "http://{}:{}/users/{}",
+            self.host,
+            self.port,
+            segment(login)
+        ));
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        default_error_handler(resp).await
+    }
+
+    pub async fn enable_user(&self, login: &str) -> CerberusResult<()> {
+        self.user_command(login, "enable", serde_json::json!({}))
+            .await
+    }
+
+    pub async fn disable_user(&self, login: &str) -> CerberusResult<()> {
+        self.user_command(login, "disable", serde_json::json!({}))
+            .await
+    }
+
+    pub async fn reset_user_password(&self, login: &str, new_password: &str) -> CerberusResult<()> {
+        let body = serde_json::json!({ "newPassword": new_password });
+
+        self.user_command(login, "reset-password", body).await
+    }
+
+    pub async fn change_user_password(
+        &self,
+        login: &str,
+        current_password: &str,
+        new_password: &str,
+    ) -> CerberusResult<()> {
+        let body = serde_json::json!({
+            "currentPassword": current_password,
+            "newPassword": new_password,
+        });
+
+        self.user_command(login, "change-password", body).await
+    }
+
+    async fn user_command(
+        &self,
+        login: &str,
+        command: &str,
+        body: serde_json::Value,
+    ) -> CerberusResult<()> {
+        let url = format!(
+            "http://{}:{}/users/{}/command/{}",
+            self.host,
+            self.port,
+            segment(login),
+            command
+        );
+
+        let req = self
+            .client
+            .post(&url)
+            .header(
+                reqwest::header::CONTENT_TYPE,
+                "application/json;charset=UTF-8",
+            )
+            .body(serde_json::to_vec(&body).unwrap());
+
+        let resp = req
+            .send()
+            .await
+            .map_err(|e| default_connection_error(self, e))?;
+
+        if resp.status().is_success() {
+            return Ok(());
+        }
+
+        default_error_handler(resp).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{segment, SubscriptionConnection};
+
+    #[test]
+    fn subscription_connection_reads_server_stats() {
+        let payload = r#"{
+            "from": "127.0.0.1:53402",
+            "username": "admin",
+            "averageItemsPerSecond": 12.5,
+            "totalItems": 4021,
+            "countSinceLastMeasurement": 25,
+            "extraStatistics": [],
+            "availableSlots": 8,
+            "inFlightMessages": 2,
+            "connectionName": "billing-worker"
+        }"#;
+        let connection: SubscriptionConnection = serde_json::from_str(payload).unwrap();
+
+        assert_eq!(connection.total_items_processed, 4021);
+        assert_eq!(connection.available_slots, 8);
+        assert_eq!(connection.in_flight_messages, 2);
+        assert_eq!(
+            connection.connection_name.as_deref(),
+            Some("billing-worker")
+        );
+    }
+
+    #[test]
+    fn segment_escapes_reserved_characters() {
+        assert_eq!(
+            segment("orders/by customer?v=2").to_string(),
+            "orders%2Fby%20customer%3Fv=2"
+        );
+        assert_eq!(segment("$by_category").to_string(), "$by_category");
+    }
 }
@@ -1,8 +1,16 @@
+pub mod acl;
 pub mod backup;
 pub mod check;
 pub mod compliance;
+pub mod consume;
+pub mod copy;
 pub mod create;
 pub mod delete;
 pub mod export;
+pub mod import;
 pub mod list;
+pub mod projection;
+pub mod query;
+pub mod subscription;
 pub mod update;
+pub mod user;
@@ -0,0 +1,555 @@
+use crate::common::{CerberusError, CerberusResult};
+use serde::{Deserialize, Serialize};
+
+const SETTINGS_STREAM: &str = "$settings";
+const SETTINGS_EVENT_TYPE: &str = "update-default-acl";
+
+/// Roles can either be a single string or a list of strings in the JSON
+/// representation the server uses.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RolesRepr {
+    Single(String),
+    Many(Vec<String>),
+}
+
+fn deserialize_roles<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let repr: Option<RolesRepr> = Option::deserialize(deserializer)?;
+
+    Ok(repr.map(|repr| match repr {
+        RolesRepr::Single(role) => vec![role],
+        RolesRepr::Many(roles) => roles,
+    }))
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq, Eq)]
+pub struct Acl {
+    #[serde(
+        rename = "$r",
+        default,
+        deserialize_with = "deserialize_roles",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub read: Option<Vec<String>>,
+
+    #[serde(
+        rename = "$w",
+        default,
+        deserialize_with = "deserialize_roles",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub write: Option<Vec<String>>,
+
+    #[serde(
+        rename = "$d",
+        default,
+        deserialize_with = "deserialize_roles",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub delete: Option<Vec<String>>,
+
+    #[serde(
+        rename = "$mr",
+        default,
+        deserialize_with = "deserialize_roles",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub meta_read: Option<Vec<String>>,
+
+    #[serde(
+        rename = "$mw",
+        default,
+        deserialize_with = "deserialize_roles",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub meta_write: Option<Vec<String>>,
+}
+
+impl Acl {
+    fn from_stream_acl(acl: eventstore::StreamAcl) -> Acl {
+        Acl {
+            read: acl.read_roles,
+            write: acl.write_roles,
+            delete: acl.delete_roles,
+            meta_read: acl.meta_read_roles,
+            meta_write: acl.meta_write_roles,
+        }
+    }
+
+    fn into_stream_acl(self) -> eventstore::StreamAcl {
+        eventstore::StreamAcl {
+            read_roles: self.read,
+            write_roles: self.write,
+            delete_roles: self.delete,
+            meta_read_roles: self.meta_read,
+            meta_write_roles: self.meta_write,
+        }
+    }
+
+    /// Roles set in `update` replace ours, the other ones are kept as-is.
+    fn merge(&self, update: Acl) -> Acl {
+        Acl {
+            read: update.read.or_else(|| self.read.clone()),
+            write: update.write.or_else(|| self.write.clone()),
+            delete: update.delete.or_else(|| self.delete.clone()),
+            meta_read: update.meta_read.or_else(|| self.meta_read.clone()),
+            meta_write: update.meta_write.or_else(|| self.meta_write.clone()),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.entries().iter().all(|(_, roles)| roles.is_none())
+    }
+
+    fn entries(&self) -> [(&'static str, &Option<Vec<String>>); 5] {
+        [
+            ("read", &self.read),
+            ("write", &self.write),
+            ("delete", &self.delete),
+            ("meta-read", &self.meta_read),
+            ("meta-write", &self.meta_write),
+        ]
+    }
+
+    /// Builds an ACL out of the --read, --write, --delete, --meta-read and
+    /// --meta-write parameters. Every role gets validated along the way.
+    fn from_args(params: &clap::ArgMatches) -> CerberusResult<Acl> {
+        let acl = Acl {
+            read: parse_roles(params, "read")?,
+            write: parse_roles(params, "write")?,
+            delete: parse_roles(params, "delete")?,
+            meta_read: parse_roles(params, "meta-read")?,
+            meta_write: parse_roles(params, "meta-write")?,
+        };
+
+        if acl.is_empty() {
+            return Err(CerberusError::user_fault(
+                "You must at least provide one of --read, --write, --delete, \
+                --meta-read or --meta-write parameters",
+            ));
+        }
+
+        Ok(acl)
+    }
+
+    fn print(&self, indent: &str) {
+        for (name, roles) in self.entries().iter() {
+            match roles {
+                Some(roles) => println!("{}{}: {}", indent, name, roles.join(", ")),
+                None => println!("{}{}: <not set>", indent, name),
+            }
+        }
+    }
+}
+
+fn parse_roles(params: &clap::ArgMatches, name: &str) -> CerberusResult<Option<Vec<String>>> {
+    let value = match params.value_of(name) {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+
+    let mut roles = Vec::new();
+
+    for role in value.split(',') {
+        let role = role.trim();
+
+        if role.is_empty() {
+            return Err(CerberusError::user_fault(format!(
+                "--{} contains an empty role: [{}]",
+                name, value
+            )));
+        }
+
+        if role.chars().any(char::is_whitespace) {
+            return Err(CerberusError::user_fault(format!(
+                "--{} role [{}] must not contain whitespaces",
+                name, role
+            )));
+        }
+
+        if !roles.iter().any(|existing| existing == role) {
+            roles.push(role.to_owned());
+        }
+    }
+
+    Ok(Some(roles))
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct DefaultAcls {
+    #[serde(
+        rename = "$userStreamAcl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    user: Option<Acl>,
+
+    #[serde(
+        rename = "$systemStreamAcl",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    system: Option<Acl>,
+}
+
+async fn read_default_acls(
+    connection: &eventstore::Connection,
+) -> CerberusResult<(DefaultAcls, eventstore::ExpectedVersion)> {
+    let status = connection
+        .read_event(SETTINGS_STREAM, -1)
+        .execute()
+        .await
+        .map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Failed to read [{}] stream: {}",
+                SETTINGS_STREAM, e
+            ))
+        })?;
+
+    match status {
+        eventstore::ReadEventStatus::Success(result) => {
+            let record = result.event.get_original_event();
+            let defaults = record.as_json().map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Latest [{}] event isn't a valid ACL settings document: {}",
+                    SETTINGS_STREAM, e
+                ))
+            })?;
+
+            Ok((
+                defaults,
+                eventstore::ExpectedVersion::Exact(record.event_number),
+            ))
+        }
+
+        eventstore::ReadEventStatus::Deleted => Err(CerberusError::user_fault(format!(
+            "Stream [{}] is deleted",
+            SETTINGS_STREAM
+        ))),
+
+        _ => Ok((
+            DefaultAcls::default(),
+            eventstore::ExpectedVersion::NoStream,
+        )),
+    }
+}
+
+async fn read_stream_acl(
+    connection: &eventstore::Connection,
+    stream: &str,
+) -> CerberusResult<(eventstore::StreamMetadata, eventstore::ExpectedVersion)> {
+    let result = connection
+        .read_stream_metadata(stream)
+        .execute()
+        .await
+        .map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Failed to read [{}] stream metadata: {}",
+                stream, e
+            ))
+        })?;
+
+    match result {
+        eventstore::StreamMetadataResult::Success(versioned) => Ok((
+            versioned.metadata,
+            eventstore::ExpectedVersion::Exact(versioned.version),
+        )),
+
+        eventstore::StreamMetadataResult::NotFound { .. } => Ok((
+            eventstore::StreamMetadata::default(),
+            eventstore::ExpectedVersion::Any,
+        )),
+
+        eventstore::StreamMetadataResult::Deleted { .. } => Err(CerberusError::user_fault(
+            format!("Stream [{}] is deleted", stream),
+        )),
+    }
+}
+
+/// Server behaviour when neither the stream nor `$settings` define a role.
+fn built_in_default(is_system: bool) -> Acl {
+    let all = Some(vec!["$all".to_owned()]);
+    let admins = Some(vec!["$admins".to_owned()]);
+
+    if is_system {
+        Acl {
+            read: admins.clone(),
+            write: admins.clone(),
+            delete: admins.clone(),
+            meta_read: admins.clone(),
+            meta_write: admins,
+        }
+    } else {
+        Acl {
+            read: all.clone(),
+            write: all.clone(),
+            delete: all.clone(),
+            meta_read: all,
+            meta_write: admins,
+        }
+    }
+}
+
+pub mod show {
+    use super::{Acl, DefaultAcls};
+    use crate::common::{CerberusResult, OutputFormat};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct EffectiveRole {
+        roles: Vec<String>,
+        source: &'static str,
+    }
+
+    #[derive(Serialize)]
+    struct EffectiveAcl {
+        stream: String,
+        read: EffectiveRole,
+        write: EffectiveRole,
+        delete: EffectiveRole,
+        meta_read: EffectiveRole,
+        meta_write: EffectiveRole,
+    }
+
+    fn resolve(
+        stream: &Option<Vec<String>>,
+        default: &Option<Vec<String>>,
+        built_in: &Option<Vec<String>>,
+    ) -> EffectiveRole {
+        if let Some(roles) = stream {
+            return EffectiveRole {
+                roles: roles.clone(),
+                source: "stream",
+            };
+        }
+
+        if let Some(roles) = default {
+            return EffectiveRole {
+                roles: roles.clone(),
+                source: "default",
+            };
+        }
+
+        EffectiveRole {
+            roles: built_in.clone().unwrap_or_default(),
+            source: "built-in",
+        }
+    }
+
+    fn compute(stream: &str, stream_acl: &Acl, defaults: &DefaultAcls) -> EffectiveAcl {
+        let is_system = stream.starts_with('$');
+        let default = if is_system {
+            defaults.system.clone()
+        } else {
+            defaults.user.clone()
+        }
+        .unwrap_or_default();
+        let built_in = super::built_in_default(is_system);
+
+        EffectiveAcl {
+            stream: stream.to_owned(),
+            read: resolve(&stream_acl.read, &default.read, &built_in.read),
+            write: resolve(&stream_acl.write, &default.write, &built_in.write),
+            delete: resolve(&stream_acl.delete, &default.delete, &built_in.delete),
+            meta_read: resolve(
+                &stream_acl.meta_read,
+                &default.meta_read,
+                &built_in.meta_read,
+            ),
+            meta_write: resolve(
+                &stream_acl.meta_write,
+                &default.meta_write,
+                &built_in.meta_write,
+            ),
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let stream = params.value_of("stream").expect("Already checked by Clap");
+        let connection = crate::common::create_connection_default(global).await?;
+        let (metadata, _) = super::read_stream_acl(&connection, stream).await?;
+        let (defaults, _) = super::read_default_acls(&connection).await?;
+        let stream_acl = Acl::from_stream_acl(metadata.acl);
+        let effective = compute(stream, &stream_acl, &defaults);
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&effective);
+        }
+
+        println!("Effective ACL of stream [{}]:", stream);
+
+        let rows = [
+            ("read", &effective.read),
+            ("write", &effective.write),
+            ("delete", &effective.delete),
+            ("meta-read", &effective.meta_read),
+            ("meta-write", &effective.meta_write),
+        ];
+
+        for (name, role) in rows.iter() {
+            println!(
+                "\t{}: {} [from {}]",
+                name,
+                role.roles.join(", "),
+                role.source
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub mod set {
+    use super::Acl;
+    use crate::common::{CerberusError, CerberusResult};
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let stream = params.value_of("stream").expect("Already checked by Clap");
+        let acl = Acl::from_args(params)?;
+        let connection = crate::common::create_connection_default(global).await?;
+        let (mut metadata, expected_version) = super::read_stream_acl(&connection, stream).await?;
+        let previous = Acl::from_stream_acl(metadata.acl.clone());
+        let acl = previous.merge(acl);
+
+        if previous == acl {
+            println!("ACL of stream [{}] is already up-to-date.", stream);
+
+            return Ok(());
+        }
+
+        println!("Previous ACL of stream [{}]:", stream);
+        previous.print("\t");
+        println!("New ACL of stream [{}]:", stream);
+        acl.print("\t");
+
+        metadata.acl = acl.into_stream_acl();
+
+        connection
+            .write_stream_metadata(stream, metadata)
+            .expected_version(expected_version)
+            .execute()
+            .await
+            .map_err(|e| {
+                CerberusError::user_fault(format!("Failed to write [{}] stream ACL: {}", stream, e))
+            })?;
+
+        println!("ACL of stream [{}] updated.", stream);
+
+        Ok(())
+    }
+}
+
+pub mod defaults {
+    use super::{Acl, DefaultAcls};
+    use crate::common::{CerberusError, CerberusResult, OutputFormat};
+
+    pub async fn show(
+        global: &clap::ArgMatches<'_>,
+        _: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let connection = crate::common::create_connection_default(global).await?;
+        let (defaults, _) = super::read_default_acls(&connection).await?;
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&defaults);
+        }
+
+        println!("User streams default ACL:");
+        defaults.user.unwrap_or_default().print("\t");
+        println!("System streams default ACL:");
+        defaults.system.unwrap_or_default().print("\t");
+
+        Ok(())
+    }
+
+    pub async fn set(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let acl = Acl::from_args(params)?;
+        let is_system = params.is_present("system");
+        let connection = crate::common::create_connection_default(global).await?;
+        let (mut defaults, expected_version): (DefaultAcls, _) =
+            super::read_default_acls(&connection).await?;
+
+        let (label, target) = if is_system {
+            ("System", &mut defaults.system)
+        } else {
+            ("User", &mut defaults.user)
+        };
+
+        let previous = target.clone().unwrap_or_default();
+        let acl = previous.merge(acl);
+
+        println!("Previous {} streams default ACL:", label.to_lowercase());
+        previous.print("\t");
+        println!("New {} streams default ACL:", label.to_lowercase());
+        acl.print("\t");
+
+        *target = Some(acl);
+
+        let event =
+            eventstore::EventData::json(super::SETTINGS_EVENT_TYPE, &defaults).map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to serialize default ACLs: {}", e))
+            })?;
+
+        connection
+            .write_events(super::SETTINGS_STREAM)
+            .push_event(event)
+            .expected_version(expected_version)
+            .execute()
+            .await
+            .map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to write to [{}] stream: {}",
+                    super::SETTINGS_STREAM,
+                    e
+                ))
+            })?;
+
+        println!("{} streams default ACL updated.", label);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Acl;
+
+    fn roles(names: &[&str]) -> Option<Vec<String>> {
+        Some(names.iter().map(|name| (*name).to_owned()).collect())
+    }
+
+    #[test]
+    fn merge_keeps_roles_not_updated() {
+        let current = Acl {
+            read: roles(&["ops"]),
+            write: roles(&["writers"]),
+            delete: roles(&["$admins"]),
+            meta_read: None,
+            meta_write: roles(&["$admins"]),
+        };
+        let update = Acl {
+            read: roles(&["ops", "support"]),
+            ..Acl::default()
+        };
+
+        assert_eq!(
+            current.merge(update),
+            Acl {
+                read: roles(&["ops", "support"]),
+                ..current.clone()
+            }
+        );
+    }
+}
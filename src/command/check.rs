@@ -112,6 +112,7 @@ pub async fn run(
     check_single_node_connection(global, tcp_port, connection).await
 }
 
+#[allow(clippy::collapsible_match)]
 async fn check_single_node_connection(
     global: &clap::ArgMatches<'_>,
     tcp_port: u16,
@@ -6,7 +6,7 @@ use std::fs::File;
 use std::io::Read;
 
 #[derive(Serialize, Deserialize, Debug)]
-struct PersistentSubscriptionSettings {
+pub(crate) struct PersistentSubscriptionSettings {
     #[serde(default)]
     pub resolve_link: bool,
 
@@ -29,7 +29,31 @@ struct PersistentSubscriptionSettings {
 }
 
 impl PersistentSubscriptionSettings {
-    fn to_sub_config(&self) -> api::SubscriptionConfig {
+    pub(crate) fn from_sub_config(config: &api::SubscriptionConfig) -> Self {
+        let strategy = match config.named_consumer_strategy {
+            api::NamedConsumerStrategy::RoundRobin => SubscriptionStrategy::RoundRobin,
+            api::NamedConsumerStrategy::DispatchToSingle => SubscriptionStrategy::DispatchToSingle,
+            api::NamedConsumerStrategy::Pinned => SubscriptionStrategy::Pinned,
+        };
+
+        PersistentSubscriptionSettings {
+            resolve_link: config.resolve_linktos,
+            start_from: config.start_from,
+            extra_stats: config.extra_statistics,
+            msg_timeout_in_ms: Some(config.message_timeout_milliseconds),
+            max_retry_count: Some(config.max_retry_count),
+            live_buffer_size: Some(config.live_buffer_size),
+            read_batch_size: Some(config.read_batch_size),
+            buffer_size: Some(config.buffer_size),
+            checkpoint_after_in_ms: Some(config.check_point_after_milliseconds),
+            min_checkpoint_count: Some(config.min_check_point_count),
+            max_checkpoint_count: Some(config.max_check_point_count),
+            max_subs_count: Some(config.max_subscriber_count),
+            strategy,
+        }
+    }
+
+    pub(crate) fn to_sub_config(&self) -> api::SubscriptionConfig {
         let named_consumer_strategy = match self.strategy {
             SubscriptionStrategy::RoundRobin => api::NamedConsumerStrategy::RoundRobin,
             SubscriptionStrategy::DispatchToSingle => api::NamedConsumerStrategy::DispatchToSingle,
@@ -56,12 +80,13 @@ impl PersistentSubscriptionSettings {
 
 #[derive(Serialize, Deserialize, Debug)]
 #[serde(rename_all = "kebab-case")]
-enum SubscriptionStrategy {
+pub(crate) enum SubscriptionStrategy {
     RoundRobin,
     DispatchToSingle,
     Pinned,
 }
 
+#[allow(clippy::derivable_impls)]
 impl Default for SubscriptionStrategy {
     fn default() -> Self {
         SubscriptionStrategy::RoundRobin
@@ -85,12 +110,12 @@ impl ProjectionType {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct Subscription {
-    stream: String,
-    group: String,
+pub(crate) struct Subscription {
+    pub(crate) stream: String,
+    pub(crate) group: String,
 
     #[serde(flatten)]
-    settings: PersistentSubscriptionSettings,
+    pub(crate) settings: PersistentSubscriptionSettings,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -115,14 +140,24 @@ struct Projection {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-struct Compliance {
+pub(crate) struct Compliance {
     #[serde(rename = "projection")]
     #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
     projections: Vec<Projection>,
 
     #[serde(rename = "subscription")]
     #[serde(default)]
-    subscriptions: Vec<Subscription>,
+    pub(crate) subscriptions: Vec<Subscription>,
+}
+
+impl Compliance {
+    pub(crate) fn from_subscriptions(subscriptions: Vec<Subscription>) -> Self {
+        Compliance {
+            projections: Vec::new(),
+            subscriptions,
+        }
+    }
 }
 
 pub async fn run(
@@ -312,6 +347,7 @@ pub async fn run(
     Ok(())
 }
 
+#[allow(clippy::match_like_matches_macro)]
 async fn perform_projection_checks(
     api: &Api<'_>,
     proj: &Projection,
@@ -0,0 +1,221 @@
+use crate::common::{CerberusError, CerberusResult};
+use colored::Colorize;
+use std::collections::HashMap;
+
+const DEFAULT_BATCH_SIZE: u16 = 10;
+
+enum Decision {
+    Ack,
+    Nak(eventstore::NakAction),
+    Quit,
+}
+
+#[derive(Default)]
+struct Summary {
+    received: usize,
+    acked: usize,
+    parked: usize,
+    retried: usize,
+    skipped: usize,
+
+    /// Highest retry count seen per event, keyed by stream and event number,
+    /// for events delivered more than once.
+    retries: HashMap<(String, i64), usize>,
+}
+
+impl Summary {
+    fn print(&self) {
+        println!("--------------------------------------------------------------");
+        println!("Received: {}", self.received);
+        println!("Acked: {}", self.acked);
+        println!(
+            "Nacked: {} parked, {} retried, {} skipped",
+            self.parked, self.retried, self.skipped
+        );
+
+        if !self.retries.is_empty() {
+            println!("Redelivered events:");
+
+            let mut retries: Vec<_> = self.retries.iter().collect();
+
+            retries.sort();
+
+            for ((stream, number), count) in retries {
+                println!("\t{}@{}: retry count {}", number, stream, count);
+            }
+        }
+    }
+}
+
+/// Reads the user decision from stdin. That happens on the blocking thread
+/// pool so the connection keeps sending heartbeats while we wait.
+async fn ask_decision() -> CerberusResult<Decision> {
+    loop {
+        let answer = tokio::task::spawn_blocking(|| {
+            use std::io::Write;
+
+            print!("[a]ck, [p]ark, [r]etry, [s]kip or [q]uit? ");
+            std::io::stdout().flush()?;
+
+            let mut answer = String::new();
+
+            std::io::stdin().read_line(&mut answer).map(|_| answer)
+        })
+        .await
+        .map_err(|e| CerberusError::dev_fault(format!("Failed to read answer: {}", e)))?
+        .map_err(|e| CerberusError::user_fault(format!("Failed to read answer: {}", e)))?;
+
+        let decision = match answer.trim() {
+            "a" | "ack" => Decision::Ack,
+            "p" | "park" => Decision::Nak(eventstore::NakAction::Park),
+            "r" | "retry" => Decision::Nak(eventstore::NakAction::Retry),
+            "s" | "skip" => Decision::Nak(eventstore::NakAction::Skip),
+            // Stdin is closed, nothing else can be decided.
+            "" if answer.is_empty() => Decision::Quit,
+            "q" | "quit" => Decision::Quit,
+            _ => continue,
+        };
+
+        return Ok(decision);
+    }
+}
+
+fn print_event(index: usize, event: &eventstore::PersistentSubEvent) {
+    let record = event.inner.get_original_event();
+    let retry = if event.retry_count > 0 {
+        format!("retry count: {}", event.retry_count).yellow()
+    } else {
+        "retry count: 0".normal()
+    };
+
+    println!("--------------------------------------------------------------");
+    println!(
+        "#{} {}@{} [{}] {} ({})",
+        index,
+        record.event_number,
+        record.event_stream_id,
+        record.event_type,
+        record.event_id,
+        retry
+    );
+    println!("{}", String::from_utf8_lossy(&record.data));
+
+    if !record.metadata.is_empty() {
+        println!("Metadata: {}", String::from_utf8_lossy(&record.metadata));
+    }
+}
+
+/// Acks and nacks get no answer from the server. A read going through the same
+/// connection only gets its answer once the server received everything sent
+/// before it, so the last decisions aren't lost when the process exits. Even
+/// a denied read proves that much, only a dropped connection doesn't.
+async fn flush(connection: &eventstore::Connection, stream: &str) -> CerberusResult<()> {
+    match connection.read_event(stream, -1).execute().await {
+        Err(eventstore::OperationError::Aborted) => Err(CerberusError::user_fault(
+            "Connection closed before the last acknowledgements could be confirmed",
+        )),
+
+        _ => Ok(()),
+    }
+}
+
+pub async fn run(
+    global: &clap::ArgMatches<'_>,
+    params: &clap::ArgMatches<'_>,
+) -> CerberusResult<()> {
+    let stream = params.value_of("stream").expect("Already checked by Clap");
+    let group_id = params
+        .value_of("group-id")
+        .expect("Already checked by Clap");
+    let auto_ack = params.is_present("auto-ack");
+
+    let max_opt: Option<usize> = match params.value_of("max") {
+        Some(param) => Some(param.parse().map_err(|e| {
+            CerberusError::user_fault(format!("Failed to parse --max number parameter: {}", e))
+        })?),
+
+        None => None,
+    };
+
+    let batch_size = match params.value_of("batch-size") {
+        Some(param) => param.parse().map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Failed to parse --batch-size number parameter: {}",
+                e
+            ))
+        })?,
+
+        None => DEFAULT_BATCH_SIZE,
+    };
+
+    let connection = crate::common::create_connection_default(global).await?;
+    let (mut sub_read, mut sub_write) = connection
+        .connect_persistent_subscription(stream, group_id)
+        .batch_size(batch_size)
+        .execute();
+
+    let mut summary = Summary::default();
+
+    println!(
+        "Connected to persistent subscription on [{}] with group [{}].",
+        stream, group_id
+    );
+
+    while max_opt.is_none_or(|max| summary.received < max) {
+        let event = match sub_read.read_next().await {
+            Some(event) => event,
+            None => {
+                summary.print();
+
+                return Err(CerberusError::user_fault(
+                    "Persistent subscription dropped by the server",
+                ));
+            }
+        };
+
+        summary.received += 1;
+        print_event(summary.received, &event);
+
+        if event.retry_count > 0 {
+            let record = event.inner.get_original_event();
+            let key = (record.event_stream_id.clone(), record.event_number);
+            let count = summary.retries.entry(key).or_default();
+
+            *count = (*count).max(event.retry_count);
+        }
+
+        let decision = if auto_ack {
+            Decision::Ack
+        } else {
+            ask_decision().await?
+        };
+
+        match decision {
+            Decision::Ack => {
+                sub_write.ack_event(event).await;
+                summary.acked += 1;
+            }
+
+            Decision::Nak(action) => {
+                match action {
+                    eventstore::NakAction::Park => summary.parked += 1,
+                    eventstore::NakAction::Retry => summary.retried += 1,
+                    _ => summary.skipped += 1,
+                }
+
+                sub_write
+                    .nak_event(event, action, "Nacked through cerberus consume")
+                    .await;
+            }
+
+            // The pending event isn't acked, the server will redeliver it
+            // once its message timeout expires.
+            Decision::Quit => break,
+        }
+    }
+
+    flush(&connection, stream).await?;
+    summary.print();
+
+    Ok(())
+}
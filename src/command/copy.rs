@@ -0,0 +1,164 @@
+pub mod events {
+    use crate::common::{record_to_event_data, CerberusError, CerberusResult};
+    use futures::TryStreamExt;
+
+    const DEFAULT_BUFFER_SIZE: usize = 500;
+
+    enum Mode {
+        Copy { regenerate_ids: bool },
+        Link,
+    }
+
+    fn parse_event_number(params: &clap::ArgMatches, name: &str) -> CerberusResult<Option<i64>> {
+        if let Some(param) = params.value_of(name) {
+            let number: i64 = param.parse().map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to parse --{} event number parameter: {}",
+                    name, e
+                ))
+            })?;
+
+            if number < 0 {
+                return Err(CerberusError::user_fault(format!(
+                    "--{} parameter must be a positive event number",
+                    name
+                )));
+            }
+
+            return Ok(Some(number));
+        }
+
+        Ok(None)
+    }
+
+    fn to_event_data(mode: &Mode, event: &eventstore::ResolvedEvent) -> eventstore::EventData {
+        match *mode {
+            Mode::Link => {
+                // When the source stream holds links, the new link must point at the
+                // event they resolve to, not at the link itself.
+                let target = event
+                    .event
+                    .as_ref()
+                    .unwrap_or_else(|| event.get_original_event());
+                let link = format!("{}@{}", target.event_number, target.event_stream_id);
+
+                eventstore::EventData::binary("$>", link.into())
+            }
+
+            Mode::Copy { regenerate_ids } => {
+                let data = record_to_event_data(event.get_original_event());
+
+                if regenerate_ids {
+                    data.id(uuid::Uuid::new_v4())
+                } else {
+                    data
+                }
+            }
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let from_stream = params
+            .value_of("from-stream")
+            .expect("from-stream presence is already checked by Clap");
+        let to_stream = params
+            .value_of("to-stream")
+            .expect("to-stream presence is already checked by Clap");
+
+        if from_stream == to_stream {
+            return Err(CerberusError::user_fault(
+                "--from-stream and --to-stream must target different streams",
+            ));
+        }
+
+        let from = parse_event_number(params, "from")?.unwrap_or(0);
+        let to_opt = parse_event_number(params, "to")?;
+
+        if let Some(to) = to_opt {
+            if to < from {
+                return Err(CerberusError::user_fault(format!(
+                    "--to event number ({}) must be greater or equal to --from ({})",
+                    to, from
+                )));
+            }
+        }
+
+        let mode = if params.is_present("as-links") {
+            Mode::Link
+        } else {
+            Mode::Copy {
+                regenerate_ids: params.is_present("regenerate-ids"),
+            }
+        };
+
+        let connection = crate::common::create_connection_default(global).await?;
+        let mut source = connection
+            .read_stream(from_stream)
+            .start_from(from)
+            .max_count(DEFAULT_BUFFER_SIZE as i32)
+            .resolve_link_tos(eventstore::LinkTos::ResolveLink)
+            .iterate_over_batch();
+
+        let mut count = 0usize;
+        let mut done = false;
+
+        while let Some(chunk) = source.try_next().await? {
+            let mut buffer = Vec::with_capacity(chunk.len());
+
+            for event in chunk {
+                if let Some(to) = to_opt {
+                    if event.get_original_event().event_number > to {
+                        done = true;
+                        break;
+                    }
+                }
+
+                buffer.push(to_event_data(&mode, &event));
+            }
+
+            if !buffer.is_empty() {
+                count += buffer.len();
+
+                info!(
+                    "Writing {} events to stream {} ...",
+                    buffer.len(),
+                    to_stream
+                );
+
+                connection
+                    .write_events(to_stream)
+                    .append_events(buffer)
+                    .execute()
+                    .await?;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        if count == 0 {
+            return Err(CerberusError::user_fault(format!(
+                "No event found in stream [{}] starting from event number {}",
+                from_stream, from
+            )));
+        }
+
+        match mode {
+            Mode::Link => println!(
+                "Linked {} events from [{}] into [{}].",
+                count, from_stream, to_stream
+            ),
+
+            Mode::Copy { .. } => println!(
+                "Copied {} events from [{}] to [{}].",
+                count, from_stream, to_stream
+            ),
+        }
+
+        Ok(())
+    }
+}
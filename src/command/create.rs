@@ -1,4 +1,5 @@
 pub mod subscription {
+    use crate::command::subscription::settings::Settings;
     use crate::common::{CerberusError, CerberusResult, User};
 
     pub async fn run(
@@ -18,134 +19,7 @@ pub mod subscription {
         let connection = crate::common::create_connection_default(global).await?;
         let mut setts = eventstore::PersistentSubscriptionSettings::default();
 
-        setts.resolve_link_tos = params.is_present("resolve-link");
-        setts.extra_stats = params.is_present("extra-stats");
-
-        if let Some(param) = params.value_of("start-from") {
-            let start_from = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --start-from number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.start_from = start_from;
-        }
-
-        if let Some(param) = params.value_of("message-timeout") {
-            let millis = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --message-timeout number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.msg_timeout = std::time::Duration::from_millis(millis);
-        }
-
-        if let Some(param) = params.value_of("max-retry-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-retry-count number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.max_retry_count = count;
-        }
-
-        if let Some(param) = params.value_of("live-buffer-size") {
-            let size = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --live-buffer-size number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.live_buf_size = size;
-        }
-
-        if let Some(param) = params.value_of("read-batch-size") {
-            let size = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --read-batch-size number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.read_batch_size = size;
-        }
-
-        if let Some(param) = params.value_of("history-buffer-size") {
-            let size = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --history-buffer-size number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.history_buf_size = size;
-        }
-
-        if let Some(param) = params.value_of("checkpoint-after") {
-            let millis = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --checkpoint-after number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.checkpoint_after = std::time::Duration::from_millis(millis);
-        }
-
-        if let Some(param) = params.value_of("min-checkpoint-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --min-checkpoint-count number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.min_checkpoint_count = count;
-        }
-
-        if let Some(param) = params.value_of("max-checkpoint-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-checkpoint-count number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.max_checkpoint_count = count;
-        }
-
-        if let Some(param) = params.value_of("max-subs-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-subs-count number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.max_subs_count = count;
-        }
-
-        if let Some(param) = params.value_of("consumer-strategy") {
-            let strategy = match param {
-                "dispatch-to-single" => eventstore::SystemConsumerStrategy::DispatchToSingle,
-                "round-robin" => eventstore::SystemConsumerStrategy::RoundRobin,
-                "pinned" => eventstore::SystemConsumerStrategy::Pinned,
-                wrong => {
-                    return Err(CerberusError::user_fault(format!(
-                        "Unknown --consumer-strategy value: [{}]",
-                        wrong
-                    )));
-                }
-            };
-
-            setts.named_consumer_strategy = strategy;
-        }
+        Settings::parse(params)?.apply_to_settings(&mut setts);
 
         let mut cmd = connection
             .create_persistent_subscription(stream_name, group_id)
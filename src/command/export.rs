@@ -1,7 +1,19 @@
-use crate::common::{CerberusError, CerberusResult};
+pub(crate) mod archive;
+mod checkpoint;
+mod progress;
+mod sink;
+mod verify;
+
+use crate::common::{CerberusError, CerberusResult, OutputFormat};
+use checkpoint::Checkpointer;
 use eventstore::{OperationError, ResolvedEvent};
 use futures::stream::Stream;
 use futures::{StreamExt, TryStreamExt};
+use progress::Progress;
+use sink::Sink;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 
 enum Selection<'a> {
     EventType(&'a str),
@@ -25,8 +37,7 @@ fn get_export_selection<'a>(params: &'a clap::ArgMatches) -> CerberusResult<Sele
 
     Err(CerberusError::user_fault(
         "No source submitted. You should at least provide \
-        --from-stream, --from-type or --from-category"
-            .to_owned(),
+        --from-stream, --from-type or --from-category",
     ))
 }
 
@@ -48,7 +59,7 @@ fn get_limit(params: &clap::ArgMatches) -> CerberusResult<Limit> {
 
         if value == 0 {
             return Err(CerberusError::user_fault(
-                "--top parameter must be greater than 0".to_owned(),
+                "--top parameter must be greater than 0",
             ));
         }
 
@@ -58,94 +69,242 @@ fn get_limit(params: &clap::ArgMatches) -> CerberusResult<Limit> {
     }
 }
 
-const DEFAULT_BUFFER_SIZE: usize = 500;
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 500;
 
-fn record_to_event_data(record: &eventstore::RecordedEvent) -> eventstore::EventData {
-    let data = if record.is_json {
-        let json: &serde_json::value::RawValue = record.as_json().unwrap();
+struct Options {
+    batch_size: usize,
 
-        eventstore::EventData::json(&*record.event_type, json).unwrap()
-    } else {
-        eventstore::EventData::binary(&*record.event_type, record.data.clone())
+    /// Number of streams copied at the same time by a category export.
+    concurrency: usize,
+}
+
+fn parse_positive(params: &clap::ArgMatches, name: &str, default: usize) -> CerberusResult<usize> {
+    let value = match params.value_of(name) {
+        Some(param) => param.parse().map_err(|e| {
+            CerberusError::user_fault(format!("Failed to parse --{} number: {}", name, e))
+        })?,
+
+        None => default,
     };
 
-    data.id(record.event_id)
+    if value == 0 {
+        return Err(CerberusError::user_fault(format!(
+            "--{} parameter must be greater than 0",
+            name
+        )));
+    }
+
+    Ok(value)
 }
 
-async fn export_by_category<S>(
+fn get_options(params: &clap::ArgMatches) -> CerberusResult<Options> {
+    Ok(Options {
+        batch_size: parse_positive(params, "batch-size", DEFAULT_BUFFER_SIZE)?,
+        concurrency: parse_positive(params, "concurrency", 1)?,
+    })
+}
+
+/// Copies a stream listed in the category source, found at `position`.
+async fn copy_category_stream(
     source_connection: &eventstore::Connection,
-    destination_connection: &eventstore::Connection,
-    mut stream: S,
-) -> CerberusResult<()>
-where
-    S: Stream<Item = Result<ResolvedEvent, OperationError>> + Unpin,
-{
-    let mut count = 1usize;
-    while let Some(event) = stream.try_next().await? {
-        let record = event
-            .event
-            .expect("Event field must be defined in this case");
-        let target_stream_name = std::string::String::from_utf8_lossy(&record.data).into_owned();
+    sink: &Sink,
+    checkpointer: &Mutex<&mut Checkpointer>,
+    progress: &Progress,
+    batch_size: usize,
+    position: i64,
+    target_stream_name: String,
+) -> CerberusResult<()> {
+    // TODO - It's possible the stream is deleted. We should skip it in
+    // such a case.
+    let mut command = source_connection
+        .read_stream(target_stream_name.as_str())
+        .max_count(batch_size as i32);
+
+    let (finished, start_opt) = {
+        let checkpointer = checkpointer.lock().unwrap();
+
+        (
+            checkpointer.is_finished(&target_stream_name),
+            checkpointer.next_stream_position(&target_stream_name),
+        )
+    };
+
+    if finished {
+        progress.skip(0, 1);
+
+        return checkpointer
+            .lock()
+            .unwrap()
+            .finish_position(position, &target_stream_name);
+    }
+
+    if let Some(start) = start_opt {
+        progress.skip(start as usize, 0);
+        command = command.start_from(start);
+    }
 
-        // TODO - It's possible the stream is deleted. We should skip it in
-        // such a case.
-        let mut inner_source = source_connection
-            .read_stream(target_stream_name.as_str())
-            .iterate_over_batch();
+    let mut inner_source = command.iterate_over_batch();
 
-        while let Some(chunk) = inner_source.try_next().await? {
-            let events = chunk.into_iter().map(|event| {
-                let record = event.event.expect("Targetted event must be defined");
+    info!("{} - Copy stream {} ...", position, target_stream_name);
 
-                record_to_event_data(&record)
-            });
+    while let Some(chunk) = inner_source.try_next().await? {
+        let records: Vec<_> = chunk
+            .into_iter()
+            .map(|event| event.event.expect("Targetted event must be defined"))
+            .collect();
 
-            info!("{} - Copy stream {} ...", count, target_stream_name);
+        let last_number = match records.last() {
+            Some(record) => record.event_number,
+            None => continue,
+        };
 
-            destination_connection
-                .write_events(target_stream_name.as_str())
-                .append_events(events)
-                .execute()
-                .await?;
+        sink.write(target_stream_name.as_str(), &records, |file| {
+            checkpointer
+                .lock()
+                .unwrap()
+                .record_stream(&target_stream_name, last_number, file)
+        })
+        .await?;
+        progress.record(&target_stream_name, &records);
+    }
+
+    sink.close(&target_stream_name)?;
+    progress.stream_done();
+    checkpointer
+        .lock()
+        .unwrap()
+        .finish_position(position, &target_stream_name)
+}
 
-            count += 1;
+/// Copies up to `concurrency` streams at the same time. Each stream is copied
+/// by a single task, which keeps its events in order.
+async fn export_by_category<S>(
+    source_connection: &eventstore::Connection,
+    sink: &Sink,
+    checkpointer: &mut Checkpointer,
+    progress: &Progress,
+    options: &Options,
+    stream: S,
+) -> CerberusResult<()>
+where
+    S: Stream<Item = Result<ResolvedEvent, OperationError>> + Unpin,
+{
+    let checkpointer = Mutex::new(checkpointer);
+    let checkpointer = &checkpointer;
+
+    stream
+        .map_err(|e| e.into())
+        .map_ok(|event| {
+            let position = event.get_original_event().event_number;
+            let record = event
+                .event
+                .expect("Event field must be defined in this case");
+            let target_stream_name =
+                std::string::String::from_utf8_lossy(&record.data).into_owned();
+
+            checkpointer.lock().unwrap().start_position(position);
+
+            copy_category_stream(
+                source_connection,
+                sink,
+                checkpointer,
+                progress,
+                options.batch_size,
+                position,
+                target_stream_name,
+            )
+        })
+        .try_buffer_unordered(options.concurrency)
+        .try_collect()
+        .await
+}
+
+/// Writes a batch of events coming from any stream, one write per stream.
+/// Events of a same stream keep their order. `position` is only recorded
+/// once every stream of the batch is written.
+async fn write_by_stream(
+    sink: &Sink,
+    checkpointer: &mut Checkpointer,
+    progress: &Progress,
+    position: i64,
+    records: Vec<eventstore::RecordedEvent>,
+) -> CerberusResult<()> {
+    let mut batches: Vec<(String, Vec<eventstore::RecordedEvent>)> = Vec::new();
+
+    for record in records {
+        match batches
+            .iter_mut()
+            .find(|(stream, _)| *stream == record.event_stream_id)
+        {
+            Some((_, batch)) => batch.push(record),
+            None => batches.push((record.event_stream_id.clone(), vec![record])),
         }
     }
 
+    let last = batches.len() - 1;
+
+    for (index, (stream, batch)) in batches.into_iter().enumerate() {
+        sink.write(&stream, &batch, |file| {
+            if index == last {
+                checkpointer.record_position(position, file)
+            } else {
+                checkpointer.record_file(file);
+                Ok(())
+            }
+        })
+        .await?;
+        progress.record(&stream, &batch);
+    }
+
     Ok(())
 }
 
 async fn export_by_type<S>(
-    destination_connection: &eventstore::Connection,
+    sink: &Sink,
+    checkpointer: &mut Checkpointer,
+    progress: &Progress,
+    options: &Options,
     mut stream: S,
 ) -> CerberusResult<()>
 where
     S: Stream<Item = Result<ResolvedEvent, OperationError>> + Unpin,
 {
+    let mut buffer = Vec::with_capacity(options.batch_size);
+    let mut position = -1;
+
     while let Some(event) = stream.try_next().await? {
+        position = event.get_original_event().event_number;
+
         let record = event
             .event
             .expect("Event field must be defined in this case");
 
-        println!(
+        debug!(
             "Copy event {} of type {} to stream {}",
             record.event_id, record.event_type, record.event_stream_id,
         );
 
-        let data = record_to_event_data(&record);
+        buffer.push(record);
 
-        destination_connection
-            .write_events(&*record.event_stream_id)
-            .push_event(data)
-            .execute()
-            .await?;
+        if buffer.len() == options.batch_size {
+            let batch = std::mem::replace(&mut buffer, Vec::with_capacity(options.batch_size));
+
+            write_by_stream(sink, checkpointer, progress, position, batch).await?;
+        }
+    }
+
+    if !buffer.is_empty() {
+        write_by_stream(sink, checkpointer, progress, position, buffer).await?;
     }
 
     Ok(())
 }
 
 async fn export_by_stream<S>(
-    destination_connection: &eventstore::Connection,
+    sink: &Sink,
+    checkpointer: &mut Checkpointer,
+    progress: &Progress,
+    options: &Options,
     source_stream_name: &str,
     mut stream: S,
 ) -> CerberusResult<()>
@@ -154,46 +313,75 @@ where
 {
     info!("Copy stream {} ...", source_stream_name);
 
-    let mut buffer = Vec::with_capacity(DEFAULT_BUFFER_SIZE);
+    let mut buffer = Vec::with_capacity(options.batch_size);
     let mut stream_name: String = "".to_string();
+    let mut position = -1;
 
     // TODO - We can do much better than this. Let's use a double while loop to only have a single
     // line of code that push events to the eventstore.
     while let Some(event) = stream.try_next().await? {
+        position = event.get_original_event().event_number;
+
         let record = event.event.expect("Event field must be defined");
         if buffer.is_empty() {
             stream_name = record.event_stream_id.clone().to_string();
         }
 
-        buffer.push(record_to_event_data(&record));
+        buffer.push(record);
 
-        if buffer.len() == DEFAULT_BUFFER_SIZE {
-            destination_connection
-                .write_events(stream_name.as_str())
-                .append_events(buffer.drain(..))
-                .execute()
-                .await?;
+        if buffer.len() == options.batch_size {
+            sink.write(stream_name.as_str(), &buffer, |file| {
+                checkpointer.record_position(position, file)
+            })
+            .await?;
+            progress.record(&stream_name, &buffer);
+            buffer.clear();
         }
     }
 
     if !buffer.is_empty() {
-        destination_connection
-            .write_events(stream_name.as_str())
-            .append_events(buffer.drain(..))
-            .execute()
-            .await?;
+        sink.write(stream_name.as_str(), &buffer, |file| {
+            checkpointer.record_position(position, file)
+        })
+        .await?;
+        progress.record(&stream_name, &buffer);
     }
 
     Ok(())
 }
 
-pub async fn run(
-    global: &clap::ArgMatches<'_>,
-    params: &clap::ArgMatches<'_>,
-) -> CerberusResult<()> {
-    let source_connection = crate::common::create_connection_default(global).await?;
+/// Size of the source, used to estimate the remaining time. A category
+/// source lists streams, other ones list events.
+async fn get_total(
+    connection: &eventstore::Connection,
+    tpe: &Selection<'_>,
+    stream_name: &str,
+    limit: &Limit,
+) -> Option<progress::Total> {
+    let count = crate::common::stream_event_count(connection, stream_name)
+        .await
+        .ok()? as usize;
+
+    let count = match limit {
+        Limit::Top(top) => count.min(*top),
+        Limit::None => count,
+    };
 
-    let to_tcp_port = params.value_of("to-tcp-port").unwrap_or_else(|| "1113");
+    match tpe {
+        Selection::StreamCategory(_) => Some(progress::Total::Streams(count)),
+        _ => Some(progress::Total::Events(count)),
+    }
+}
+
+fn get_compression(params: &clap::ArgMatches, path: &Path) -> CerberusResult<archive::Compression> {
+    match params.value_of("compress") {
+        Some(value) => archive::Compression::parse(value),
+        None => Ok(archive::Compression::from_path(path)),
+    }
+}
+
+fn get_destination_endpoint(params: &clap::ArgMatches) -> CerberusResult<SocketAddr> {
+    let to_tcp_port = params.value_of("to-tcp-port").unwrap_or("1113");
     let to_host = params
         .value_of("to-host")
         .expect("to-host presence is already checked by Clap");
@@ -202,22 +390,142 @@ pub async fn run(
         .parse()
         .map_err(|e| CerberusError::user_fault(format!("--to-tcp-port parse error: {:?}", e)))?;
 
-    let endpoint = format!("{}:{}", to_host, to_tcp_port)
-        .parse()
-        .map_err(|e| {
-            CerberusError::user_fault(format!("Failed to parse destination endpoint: {}", e))
+    format!("{}:{}", to_host, to_tcp_port).parse().map_err(|e| {
+        CerberusError::user_fault(format!("Failed to parse destination endpoint: {}", e))
+    })
+}
+
+/// Reopens the destination to read the exported events back.
+async fn get_destination(params: &clap::ArgMatches<'_>) -> CerberusResult<verify::Destination> {
+    if let Some(path) = params.value_of("to-file") {
+        return Ok(verify::Destination::File(PathBuf::from(path)));
+    }
+
+    if let Some(root) = params.value_of("to-dir") {
+        return Ok(verify::Destination::Directory(PathBuf::from(root)));
+    }
+
+    let connection = eventstore::Connection::builder()
+        .single_node_connection(get_destination_endpoint(params)?)
+        .await;
+
+    Ok(verify::Destination::Database(connection))
+}
+
+/// When resuming an export, files written by the previous run are cut back
+/// to the length recorded by the checkpoint and appended to.
+async fn create_sink(
+    params: &clap::ArgMatches<'_>,
+    checkpointer: &Checkpointer,
+) -> CerberusResult<Sink> {
+    if let Some(path) = params.value_of("to-file") {
+        let path = Path::new(path);
+        let compression = get_compression(params, path)?;
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        let writer = archive::Writer::open(path, compression, checkpointer.file_length(&name))?;
+
+        return Ok(Sink::File {
+            path: path.to_path_buf(),
+            name,
+            writer: Mutex::new(writer),
+            durable: checkpointer.is_enabled(),
+        });
+    }
+
+    if let Some(root) = params.value_of("to-dir") {
+        let root = PathBuf::from(root);
+        let compression = match params.value_of("compress") {
+            Some(value) => archive::Compression::parse(value)?,
+            None => archive::Compression::None,
+        };
+
+        std::fs::create_dir_all(&root).map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Failed to create [{}] directory: {}",
+                root.display(),
+                e
+            ))
         })?;
 
+        return Ok(Sink::Directory {
+            root,
+            compression,
+            files: Mutex::new(sink::StreamFiles::new(checkpointer.file_lengths())),
+            durable: checkpointer.is_enabled(),
+        });
+    }
+
+    let endpoint = get_destination_endpoint(params)?;
+    let destination_connection = eventstore::Connection::builder()
+        .single_node_connection(endpoint)
+        .await;
+
+    Ok(Sink::Database {
+        connection: destination_connection,
+        endpoint: endpoint.to_string(),
+    })
+}
+
+pub async fn run(
+    global: &clap::ArgMatches<'_>,
+    params: &clap::ArgMatches<'_>,
+) -> CerberusResult<()> {
+    let source_connection = crate::common::create_connection_default(global).await?;
+
     let tpe = get_export_selection(params)?;
     let stream_name = get_stream_name(&tpe);
-    let command = source_connection
-        .read_stream(stream_name)
-        .resolve_link_tos(eventstore::LinkTos::ResolveLink);
+    let format = OutputFormat::from_args(global);
 
     let limit = get_limit(params)?;
 
+    if params.is_present("verify-only") {
+        let destination = get_destination(params).await?;
+
+        return verify::run(
+            &source_connection,
+            &tpe,
+            &stream_name,
+            &limit,
+            destination,
+            format,
+        )
+        .await;
+    }
+
+    let options = get_options(params)?;
+    let mut checkpointer = Checkpointer::load(params.value_of("checkpoint-file"), &stream_name)?;
+    let total = get_total(&source_connection, &tpe, &stream_name, &limit).await;
+    let sink = create_sink(params, &checkpointer).await?;
+    let progress = Progress::new(
+        &stream_name,
+        sink.describe(),
+        total,
+        checkpointer.is_resumed(),
+    );
+
+    let mut command = source_connection
+        .read_stream(stream_name.as_str())
+        .resolve_link_tos(eventstore::LinkTos::ResolveLink);
+
+    if let Some(start) = checkpointer.next_position() {
+        // By category, every position of the source is a stream. Otherwise,
+        // it's an event.
+        match tpe {
+            Selection::StreamCategory(_) => progress.skip(0, start as usize),
+            _ => progress.skip(start as usize, 0),
+        }
+
+        command = command.start_from(start);
+    }
+
     let stream: Box<dyn Stream<Item = Result<ResolvedEvent, OperationError>> + Unpin> =
         if let Limit::Top(limit) = limit {
+            // Reading from the end ignores the checkpoint, which is why Clap
+            // refuses --checkpoint-file along with --top and --recent.
             let stream = command
                 .start_from_end_of_stream()
                 .iterate_over()
@@ -228,19 +536,52 @@ pub async fn run(
             Box::new(command.iterate_over())
         };
 
-    let destination_connection = eventstore::Connection::builder()
-        .single_node_connection(endpoint)
-        .await;
-
     match tpe {
         Selection::StreamCategory(_) => {
-            export_by_category(&source_connection, &destination_connection, stream).await
+            export_by_category(
+                &source_connection,
+                &sink,
+                &mut checkpointer,
+                &progress,
+                &options,
+                stream,
+            )
+            .await?
         }
 
-        Selection::EventType(_) => export_by_type(&destination_connection, stream).await,
+        Selection::EventType(_) => {
+            export_by_type(&sink, &mut checkpointer, &progress, &options, stream).await?
+        }
 
         Selection::Stream(source_stream_name) => {
-            export_by_stream(&destination_connection, source_stream_name, stream).await
+            export_by_stream(
+                &sink,
+                &mut checkpointer,
+                &progress,
+                &options,
+                source_stream_name,
+                stream,
+            )
+            .await?
         }
     }
+
+    sink.finish()?;
+    progress.print_report(format)?;
+
+    if params.is_present("verify") {
+        let destination = get_destination(params).await?;
+
+        verify::run(
+            &source_connection,
+            &tpe,
+            &stream_name,
+            &limit,
+            destination,
+            format,
+        )
+        .await?;
+    }
+
+    Ok(())
 }
@@ -0,0 +1,524 @@
+//! JSON Lines event archives. Every line holds an event along with its
+//! stream, so an archive can be read back by `import` or used as
+//! `projection test` input.
+use crate::common::{CerberusError, CerberusResult};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    #[default]
+    Json,
+    Base64,
+}
+
+impl Encoding {
+    fn is_json(&self) -> bool {
+        *self == Encoding::Json
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ArchivedEvent {
+    pub stream: String,
+    pub number: i64,
+    pub id: String,
+
+    #[serde(rename = "type")]
+    pub event_type: String,
+
+    #[serde(default)]
+    pub created: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Encoding::is_json")]
+    pub data_encoding: Encoding,
+
+    #[serde(default)]
+    pub data: serde_json::Value,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Encoding::is_json")]
+    pub metadata_encoding: Encoding,
+
+    #[serde(default)]
+    pub metadata: serde_json::Value,
+}
+
+/// JSON payloads are kept as-is so the archive stays readable. Anything else
+/// is base64 encoded.
+fn encode_payload(payload: &[u8], is_json: bool) -> (Encoding, serde_json::Value) {
+    if payload.is_empty() {
+        return (Encoding::Json, serde_json::Value::Null);
+    }
+
+    if is_json {
+        if let Ok(value) = serde_json::from_slice(payload) {
+            return (Encoding::Json, value);
+        }
+    }
+
+    (
+        Encoding::Base64,
+        serde_json::Value::String(base64::encode(payload)),
+    )
+}
+
+fn format_created(created_epoch: Option<i64>) -> Option<String> {
+    use chrono::TimeZone;
+
+    let created = chrono::Utc.timestamp_millis_opt(created_epoch?).single()?;
+
+    Some(created.to_rfc3339_opts(chrono::SecondsFormat::Millis, true))
+}
+
+impl ArchivedEvent {
+    pub fn from_record(record: &eventstore::RecordedEvent) -> Self {
+        let (data_encoding, data) = encode_payload(&record.data, record.is_json);
+        // There is no flag telling us if the metadata is JSON, so we just try.
+        let (metadata_encoding, metadata) = encode_payload(&record.metadata, true);
+
+        ArchivedEvent {
+            stream: record.event_stream_id.clone(),
+            number: record.event_number,
+            id: record.event_id.to_string(),
+            event_type: record.event_type.clone(),
+            created: format_created(record.created_epoch),
+            data_encoding,
+            data,
+            metadata_encoding,
+            metadata,
+        }
+    }
+}
+
+fn decode_payload(
+    id: &str,
+    name: &str,
+    encoding: Encoding,
+    value: &serde_json::Value,
+) -> CerberusResult<Vec<u8>> {
+    match (encoding, value) {
+        (Encoding::Base64, serde_json::Value::String(encoded)) => {
+            base64::decode(encoded).map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Event [{}] {} isn't valid base64: {}",
+                    id, name, e
+                ))
+            })
+        }
+
+        (Encoding::Base64, _) => Err(CerberusError::user_fault(format!(
+            "Event [{}] {} is base64 encoded but isn't a string",
+            id, name
+        ))),
+
+        (Encoding::Json, serde_json::Value::Null) => Ok(Vec::new()),
+
+        (Encoding::Json, value) => serde_json::to_vec(value).map_err(|e| {
+            CerberusError::dev_fault(format!("Failed to serialize event {}: {}", name, e))
+        }),
+    }
+}
+
+impl ArchivedEvent {
+    /// Decoded data and metadata bytes.
+    pub fn payloads(&self) -> CerberusResult<(Vec<u8>, Vec<u8>)> {
+        let data = decode_payload(&self.id, "data", self.data_encoding, &self.data)?;
+        let metadata =
+            decode_payload(&self.id, "metadata", self.metadata_encoding, &self.metadata)?;
+
+        Ok((data, metadata))
+    }
+
+    /// Rebuilds the event to write, keeping its original id.
+    pub fn to_event_data(&self) -> CerberusResult<eventstore::EventData> {
+        let id: uuid::Uuid = self.id.parse().map_err(|e| {
+            CerberusError::user_fault(format!("Invalid event id [{}]: {}", self.id, e))
+        })?;
+
+        let data = match self.data_encoding {
+            Encoding::Json => {
+                eventstore::EventData::json(&*self.event_type, &self.data).map_err(|e| {
+                    CerberusError::dev_fault(format!("Failed to serialize event data: {}", e))
+                })?
+            }
+
+            Encoding::Base64 => eventstore::EventData::binary(
+                &*self.event_type,
+                decode_payload(&self.id, "data", Encoding::Base64, &self.data)?.into(),
+            ),
+        };
+
+        let metadata =
+            decode_payload(&self.id, "metadata", self.metadata_encoding, &self.metadata)?;
+        let data = if metadata.is_empty() {
+            data
+        } else {
+            data.metadata_as_binary(metadata.into())
+        };
+
+        Ok(data.id(id))
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    pub fn parse(value: &str) -> CerberusResult<Compression> {
+        match value {
+            "none" => Ok(Compression::None),
+            "gzip" => Ok(Compression::Gzip),
+            "zstd" => Ok(Compression::Zstd),
+            wrong => Err(CerberusError::user_fault(format!(
+                "Unknown --compress value: [{}]",
+                wrong
+            ))),
+        }
+    }
+
+    pub fn from_path(path: &Path) -> Compression {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("gz") => Compression::Gzip,
+            Some("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Compression::None => "jsonl",
+            Compression::Gzip => "jsonl.gz",
+            Compression::Zstd => "jsonl.zst",
+        }
+    }
+}
+
+enum Encoder {
+    /// Uncompressed file, or compressed one between two members.
+    Plain(BufWriter<File>),
+    Gzip(flate2::write::GzEncoder<BufWriter<File>>),
+    Zstd(zstd::Encoder<'static, BufWriter<File>>),
+
+    /// Only left behind when switching encoders failed.
+    Closed,
+}
+
+impl Encoder {
+    fn start(inner: BufWriter<File>, compression: Compression) -> CerberusResult<Encoder> {
+        let encoder = match compression {
+            Compression::None => Encoder::Plain(inner),
+            Compression::Gzip => Encoder::Gzip(flate2::write::GzEncoder::new(
+                inner,
+                flate2::Compression::default(),
+            )),
+            Compression::Zstd => Encoder::Zstd(zstd::Encoder::new(inner, 0)?),
+        };
+
+        Ok(encoder)
+    }
+
+    /// Writes the compression trailer, if any, and gives the file back.
+    fn finish(self) -> CerberusResult<BufWriter<File>> {
+        match self {
+            Encoder::Plain(w) => Ok(w),
+            Encoder::Gzip(w) => Ok(w.finish()?),
+            Encoder::Zstd(w) => Ok(w.finish()?),
+            Encoder::Closed => Err(CerberusError::dev_fault(
+                "Archive writer used after a failure",
+            )),
+        }
+    }
+}
+
+/// Writes archived events, one per line, to a possibly compressed file.
+pub struct Writer {
+    compression: Compression,
+    encoder: Encoder,
+}
+
+impl Writer {
+    /// Opens an archive, cut back to `length` bytes and appended to when
+    /// given, truncated otherwise. Compressed events are written as new
+    /// members, which decoders read transparently.
+    pub fn open(
+        path: &Path,
+        compression: Compression,
+        length: Option<u64>,
+    ) -> CerberusResult<Writer> {
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(length.is_none())
+            .open(path)
+            .map_err(|e| {
+                CerberusError::user_fault(format!("Failed to open [{}]: {}", path.display(), e))
+            })?;
+
+        if let Some(length) = length {
+            file.set_len(length)?;
+            file.seek(SeekFrom::End(0))?;
+        }
+
+        Ok(Writer {
+            compression,
+            encoder: Encoder::Plain(BufWriter::new(file)),
+        })
+    }
+
+    pub fn write(&mut self, event: &ArchivedEvent) -> CerberusResult<()> {
+        let line = serde_json::to_vec(event).map_err(|e| {
+            CerberusError::dev_fault(format!("Failed to serialize archived event: {}", e))
+        })?;
+
+        // A compressed member is only started once there is something to
+        // put in it.
+        self.encoder = match std::mem::replace(&mut self.encoder, Encoder::Closed) {
+            Encoder::Plain(inner) => Encoder::start(inner, self.compression)?,
+            encoder => encoder,
+        };
+
+        let output: &mut dyn Write = match &mut self.encoder {
+            Encoder::Plain(w) => w,
+            Encoder::Gzip(w) => w,
+            Encoder::Zstd(w) => w,
+            Encoder::Closed => unreachable!("Encoder was just started"),
+        };
+
+        output.write_all(&line)?;
+        output.write_all(b"\n")?;
+
+        Ok(())
+    }
+
+    /// Ends the current compressed member and syncs the file to disk. Returns
+    /// the file length, which the file can be cut back to later on.
+    pub fn commit(&mut self) -> CerberusResult<u64> {
+        let mut inner = std::mem::replace(&mut self.encoder, Encoder::Closed).finish()?;
+
+        inner.flush()?;
+        inner.get_ref().sync_data()?;
+
+        let length = inner.get_ref().metadata()?.len();
+
+        self.encoder = Encoder::Plain(inner);
+
+        Ok(length)
+    }
+
+    /// Writes the compression trailer, if any, and flushes the file. Returns
+    /// the file length.
+    pub fn finish(self) -> CerberusResult<u64> {
+        let mut inner = self.encoder.finish()?;
+
+        inner.flush()?;
+
+        Ok(inner.get_ref().metadata()?.len())
+    }
+}
+
+/// Reads archived events back, detecting the compression from the file
+/// content.
+pub struct Reader {
+    path: String,
+    lines: std::io::Lines<Box<dyn BufRead>>,
+    line_number: usize,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+impl Reader {
+    pub fn open(path: &Path) -> CerberusResult<Reader> {
+        let file = File::open(path).map_err(|e| {
+            CerberusError::user_fault(format!("Failed to open [{}]: {}", path.display(), e))
+        })?;
+
+        let mut input = BufReader::new(file);
+        let magic = input.fill_buf()?;
+
+        // Compressed archives can hold several members when they were
+        // appended to, both decoders read through all of them.
+        let decoded: Box<dyn Read> = if magic.starts_with(GZIP_MAGIC) {
+            Box::new(flate2::bufread::MultiGzDecoder::new(input))
+        } else if magic.starts_with(ZSTD_MAGIC) {
+            Box::new(zstd::Decoder::with_buffer(input)?)
+        } else {
+            Box::new(input)
+        };
+
+        let lines: Box<dyn BufRead> = Box::new(BufReader::new(decoded));
+
+        Ok(Reader {
+            path: path.display().to_string(),
+            lines: lines.lines(),
+            line_number: 0,
+        })
+    }
+}
+
+impl Iterator for Reader {
+    type Item = CerberusResult<ArchivedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+
+            self.line_number += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return Some(serde_json::from_str(&line).map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to parse [{}] line {}: {}",
+                    self.path, self.line_number, e
+                ))
+            }));
+        }
+    }
+}
+
+/// Tells if a file name has one of the extensions archives are written with.
+pub fn is_archive_file(path: &Path) -> bool {
+    let name = match path.file_name() {
+        Some(name) => name.to_string_lossy(),
+        None => return false,
+    };
+
+    [Compression::None, Compression::Gzip, Compression::Zstd]
+        .iter()
+        .any(|compression| name.ends_with(&format!(".{}", compression.extension())))
+}
+
+/// Turns a stream name into a file name. Characters that aren't safe in a
+/// file name are percent-encoded so two streams never share a file.
+pub fn stream_file_name(stream: &str, compression: Compression) -> String {
+    let mut name = String::with_capacity(stream.len());
+
+    for c in stream.chars() {
+        if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' {
+            name.push(c);
+        } else {
+            let mut buf = [0; 4];
+
+            for byte in c.encode_utf8(&mut buf).bytes() {
+                name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+
+    format!("{}.{}", name, compression.extension())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn archived(data: serde_json::Value, metadata: serde_json::Value) -> ArchivedEvent {
+        ArchivedEvent {
+            stream: "orders-1".to_owned(),
+            number: 0,
+            id: "9f3a5c5e-8a4b-4d6e-9c1f-2b7d0e3a4f51".to_owned(),
+            event_type: "OrderPlaced".to_owned(),
+            created: None,
+            data_encoding: Encoding::Json,
+            data,
+            metadata_encoding: Encoding::Json,
+            metadata,
+        }
+    }
+
+    #[test]
+    fn payloads_round_trip_json_and_binary() {
+        let (data_encoding, data) = encode_payload(br#"{"total":12}"#, true);
+        let (metadata_encoding, metadata) = encode_payload(&[0, 159, 146, 150], true);
+        let mut event = archived(data, metadata);
+        event.data_encoding = data_encoding;
+        event.metadata_encoding = metadata_encoding;
+
+        assert_eq!(event.data_encoding, Encoding::Json);
+        assert_eq!(event.metadata_encoding, Encoding::Base64);
+
+        let (data, metadata) = event.payloads().unwrap();
+
+        assert_eq!(data, br#"{"total":12}"#.to_vec());
+        assert_eq!(metadata, vec![0, 159, 146, 150]);
+    }
+
+    #[test]
+    fn payloads_treat_null_as_empty() {
+        let event = archived(serde_json::Value::Null, serde_json::Value::Null);
+
+        assert_eq!(event.payloads().unwrap(), (Vec::new(), Vec::new()));
+    }
+
+    #[test]
+    fn payloads_reject_invalid_base64() {
+        let mut event = archived(serde_json::json!("not base64!"), serde_json::Value::Null);
+        event.data_encoding = Encoding::Base64;
+
+        assert!(event.payloads().is_err());
+        assert!(event.to_event_data().is_err());
+
+        event.data = serde_json::json!({ "total": 12 });
+
+        assert!(event.payloads().is_err());
+    }
+
+    #[test]
+    fn writer_cuts_files_back_to_a_committed_length() {
+        let path =
+            std::env::temp_dir().join(format!("cerberus-archive-{}.jsonl.gz", std::process::id()));
+        let event = |number| {
+            let mut event = archived(serde_json::json!({ "total": number }), Value::Null);
+            event.number = number;
+            event
+        };
+
+        let mut writer = Writer::open(&path, Compression::Gzip, None).unwrap();
+        writer.write(&event(0)).unwrap();
+        writer.write(&event(1)).unwrap();
+        let length = writer.commit().unwrap();
+        writer.write(&event(2)).unwrap();
+        assert!(writer.finish().unwrap() > length);
+
+        // Event 2 was written after the commit and is dropped.
+        let mut writer = Writer::open(&path, Compression::Gzip, Some(length)).unwrap();
+        writer.write(&event(3)).unwrap();
+        writer.finish().unwrap();
+
+        let numbers: Vec<i64> = Reader::open(&path)
+            .unwrap()
+            .map(|event| event.unwrap().number)
+            .collect();
+
+        assert_eq!(numbers, vec![0, 1, 3]);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn to_event_data_checks_the_id() {
+        let mut event = archived(serde_json::json!({ "total": 12 }), serde_json::Value::Null);
+
+        assert!(event.to_event_data().is_ok());
+
+        event.id = "not-a-uuid".to_owned();
+
+        assert!(event.to_event_data().is_err());
+    }
+}
@@ -0,0 +1,332 @@
+//! Export progress, saved to a file so an interrupted export can resume
+//! where it stopped instead of copying everything again.
+use crate::common::{CerberusError, CerberusResult};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+#[derive(Serialize, Deserialize, Default)]
+struct State {
+    /// Stream the export reads from, like `$ce-orders` or `$et-OrderPlaced`.
+    source: String,
+
+    /// Last event number of the source stream fully copied.
+    #[serde(default)]
+    position: Option<i64>,
+
+    /// Last event number copied for streams still in progress.
+    #[serde(default)]
+    streams: BTreeMap<String, i64>,
+
+    /// Streams completely copied while an earlier one of the source wasn't
+    /// yet, including the ones without any event.
+    #[serde(default)]
+    finished: BTreeSet<String>,
+
+    /// Length of the archive files, by file name, matching the progress
+    /// above. A resumed export cuts them back to it, dropping any event
+    /// written after the checkpoint was saved.
+    #[serde(default)]
+    files: BTreeMap<String, u64>,
+}
+
+pub struct Checkpointer {
+    path_opt: Option<PathBuf>,
+    state: State,
+    resumed: bool,
+
+    /// Source positions of the streams being copied.
+    in_flight: BTreeSet<i64>,
+
+    /// Source positions of the streams copied while an earlier one is still
+    /// in flight, so the recorded position can't move past them yet.
+    done: BTreeMap<i64, String>,
+    dispatched: Option<i64>,
+}
+
+impl Checkpointer {
+    pub fn load(path_opt: Option<&str>, source: &str) -> CerberusResult<Checkpointer> {
+        let mut checkpointer = Checkpointer {
+            path_opt: path_opt.map(PathBuf::from),
+            state: State {
+                source: source.to_owned(),
+                ..State::default()
+            },
+            resumed: false,
+            in_flight: BTreeSet::new(),
+            done: BTreeMap::new(),
+            dispatched: None,
+        };
+
+        let path = match checkpointer.path_opt.as_ref() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(checkpointer),
+        };
+
+        let content = std::fs::read(path)?;
+        let state: State = serde_json::from_slice(&content).map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Failed to parse checkpoint file [{}]: {}",
+                path.display(),
+                e
+            ))
+        })?;
+
+        if state.source != source {
+            return Err(CerberusError::user_fault(format!(
+                "Checkpoint file [{}] belongs to an export of [{}], not [{}]",
+                path.display(),
+                state.source,
+                source
+            )));
+        }
+
+        checkpointer.state = state;
+        checkpointer.resumed = true;
+
+        Ok(checkpointer)
+    }
+
+    pub fn is_resumed(&self) -> bool {
+        self.resumed
+    }
+
+    /// Tells if progress is saved at all, in which case archive files must
+    /// be synced to disk before recording anything.
+    pub fn is_enabled(&self) -> bool {
+        self.path_opt.is_some()
+    }
+
+    /// Length of an archive file when the checkpoint was saved.
+    pub fn file_length(&self, file: &str) -> Option<u64> {
+        self.state.files.get(file).copied()
+    }
+
+    pub fn file_lengths(&self) -> HashMap<String, u64> {
+        self.state
+            .files
+            .iter()
+            .map(|(file, length)| (file.clone(), *length))
+            .collect()
+    }
+
+    /// Tells if a stream of a category was completely copied already.
+    pub fn is_finished(&self, stream: &str) -> bool {
+        self.state.finished.contains(stream)
+    }
+
+    /// Where to start reading the source stream from.
+    pub fn next_position(&self) -> Option<i64> {
+        self.state.position.map(|position| position + 1)
+    }
+
+    /// Where to start reading a stream from, if it was partially copied.
+    pub fn next_stream_position(&self, stream: &str) -> Option<i64> {
+        self.state.streams.get(stream).map(|number| number + 1)
+    }
+
+    /// Records the length of an archive file once synced, without saving it
+    /// yet.
+    pub fn record_file(&mut self, file: Option<(&str, u64)>) {
+        if let Some((file, length)) = file {
+            self.state.files.insert(file.to_owned(), length);
+        }
+    }
+
+    /// Records a batch of events written to a stream, up to `last_number`.
+    pub fn record_stream(
+        &mut self,
+        stream: &str,
+        last_number: i64,
+        file: Option<(&str, u64)>,
+    ) -> CerberusResult<()> {
+        self.record_file(file);
+        self.state.streams.insert(stream.to_owned(), last_number);
+
+        self.save()
+    }
+
+    /// Records that the source stream was processed up to `position`.
+    pub fn record_position(
+        &mut self,
+        position: i64,
+        file: Option<(&str, u64)>,
+    ) -> CerberusResult<()> {
+        self.record_file(file);
+        self.state.position = Some(position);
+
+        self.save()
+    }
+
+    /// Marks the stream found at `position` in the source as being copied.
+    pub fn start_position(&mut self, position: i64) {
+        self.in_flight.insert(position);
+        self.dispatched = self.dispatched.max(Some(position));
+    }
+
+    /// Marks the stream found at `position` as completely copied. The recorded
+    /// position only moves up to the earliest stream still in flight. Streams
+    /// completed past it are listed as finished, so a resumed export doesn't
+    /// copy them again.
+    pub fn finish_position(&mut self, position: i64, stream: &str) -> CerberusResult<()> {
+        self.in_flight.remove(&position);
+        self.done.insert(position, stream.to_owned());
+        self.state.streams.remove(stream);
+        self.state.finished.insert(stream.to_owned());
+
+        let committed = match self.in_flight.iter().next() {
+            Some(earliest) => earliest - 1,
+            None => self.dispatched.unwrap_or(position),
+        };
+
+        let remaining = self.done.split_off(&(committed + 1));
+
+        for stream in self.done.values() {
+            self.state.finished.remove(stream);
+        }
+
+        self.done = remaining;
+
+        if self.state.position < Some(committed) {
+            self.state.position = Some(committed);
+        }
+
+        self.save()
+    }
+
+    fn save(&self) -> CerberusResult<()> {
+        let path = match self.path_opt.as_ref() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        let content = serde_json::to_vec_pretty(&self.state).map_err(|e| {
+            CerberusError::dev_fault(format!("Failed to serialize export checkpoint: {}", e))
+        })?;
+
+        // Writes to a temporary file first so an interrupted export never
+        // leaves a truncated checkpoint behind.
+        let tmp = path.with_extension("tmp");
+
+        std::fs::write(&tmp, content)?;
+        std::fs::rename(&tmp, path)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn checkpoint_path(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "cerberus-checkpoint-{}-{}.json",
+            std::process::id(),
+            name
+        ));
+
+        let _ = std::fs::remove_file(&path);
+
+        path
+    }
+
+    fn load(path: &std::path::Path, source: &str) -> CerberusResult<Checkpointer> {
+        Checkpointer::load(path.to_str(), source)
+    }
+
+    #[test]
+    fn starts_fresh_without_a_file() {
+        let path = checkpoint_path("fresh");
+        let checkpointer = load(&path, "orders").unwrap();
+
+        assert!(checkpointer.is_enabled());
+        assert!(!checkpointer.is_resumed());
+        assert_eq!(checkpointer.next_position(), None);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn resumes_from_the_saved_progress() {
+        let path = checkpoint_path("resume");
+        let mut checkpointer = load(&path, "$et-OrderPlaced").unwrap();
+
+        checkpointer
+            .record_stream("orders-1", 4, Some(("orders-1.jsonl", 120)))
+            .unwrap();
+        checkpointer
+            .record_position(9, Some(("orders-2.jsonl", 80)))
+            .unwrap();
+
+        let resumed = load(&path, "$et-OrderPlaced").unwrap();
+
+        assert!(resumed.is_resumed());
+        assert_eq!(resumed.next_position(), Some(10));
+        assert_eq!(resumed.next_stream_position("orders-1"), Some(5));
+        assert_eq!(resumed.next_stream_position("orders-2"), None);
+        assert_eq!(resumed.file_length("orders-1.jsonl"), Some(120));
+        assert_eq!(resumed.file_length("orders-2.jsonl"), Some(80));
+
+        assert!(load(&path, "$et-OrderShipped").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn saves_through_a_temporary_file() {
+        let path = checkpoint_path("atomic");
+        let tmp = path.with_extension("tmp");
+
+        // Left behind by an export killed while saving.
+        std::fs::write(&tmp, b"{\"source\":").unwrap();
+        std::fs::write(&path, b"{\"source\":\"orders\",\"position\":3}").unwrap();
+
+        let mut checkpointer = load(&path, "orders").unwrap();
+
+        assert_eq!(checkpointer.next_position(), Some(4));
+
+        checkpointer.record_position(7, None).unwrap();
+
+        assert!(!tmp.exists());
+        assert_eq!(load(&path, "orders").unwrap().next_position(), Some(8));
+
+        std::fs::write(&path, b"{\"source\":").unwrap();
+
+        assert!(load(&path, "orders").is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn finished_positions_wait_for_earlier_streams() {
+        let path = checkpoint_path("finish");
+        let mut checkpointer = load(&path, "$ce-orders").unwrap();
+
+        checkpointer.start_position(0);
+        checkpointer.start_position(1);
+        checkpointer.start_position(2);
+        checkpointer.record_stream("orders-1", 3, None).unwrap();
+
+        // Streams without any event are finished right away.
+        checkpointer.finish_position(1, "orders-2").unwrap();
+        checkpointer.finish_position(2, "orders-3").unwrap();
+
+        let resumed = load(&path, "$ce-orders").unwrap();
+
+        assert_eq!(resumed.next_position(), Some(0));
+        assert_eq!(resumed.next_stream_position("orders-1"), Some(4));
+        assert!(resumed.is_finished("orders-2"));
+        assert!(resumed.is_finished("orders-3"));
+
+        checkpointer.finish_position(0, "orders-1").unwrap();
+
+        let resumed = load(&path, "$ce-orders").unwrap();
+
+        assert_eq!(resumed.next_position(), Some(3));
+        assert_eq!(resumed.next_stream_position("orders-1"), None);
+        assert!(!resumed.is_finished("orders-2"));
+        assert!(!resumed.is_finished("orders-3"));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
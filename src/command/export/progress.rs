@@ -0,0 +1,252 @@
+//! Export progress display and final report. Progress goes to stderr so the
+//! report can be piped when written as JSON.
+use crate::common::{CerberusResult, OutputFormat};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// ANSI sequence clearing the current terminal line.
+const CLEAR_LINE: &str = "\r\x1B[2K";
+
+/// Size of the source, when it can be known upfront.
+pub enum Total {
+    Events(usize),
+    Streams(usize),
+}
+
+pub struct Progress {
+    source: String,
+    destination: String,
+    total: Option<Total>,
+    resumed: bool,
+    started: Instant,
+    interactive: bool,
+    last_display: Mutex<Instant>,
+    events: AtomicUsize,
+    bytes: AtomicU64,
+    streams: Mutex<HashSet<String>>,
+    streams_done: AtomicUsize,
+    skipped_events: AtomicUsize,
+    skipped_streams: AtomicUsize,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Report {
+    source: String,
+    destination: String,
+    resumed: bool,
+    events: usize,
+    streams: usize,
+    bytes: u64,
+    skipped_events: usize,
+    skipped_streams: usize,
+    elapsed_secs: f64,
+    events_per_sec: f64,
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+
+    let mut value = bytes as f64;
+    let mut unit = 0;
+
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    match (secs / 3_600, (secs % 3_600) / 60, secs % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+fn rate(count: usize, elapsed: Duration) -> f64 {
+    let secs = elapsed.as_secs_f64();
+
+    if secs > 0.0 {
+        count as f64 / secs
+    } else {
+        0.0
+    }
+}
+
+impl Progress {
+    pub fn new(source: &str, destination: String, total: Option<Total>, resumed: bool) -> Self {
+        let now = Instant::now();
+
+        Progress {
+            source: source.to_owned(),
+            destination,
+            total,
+            resumed,
+            started: now,
+            interactive: std::io::stderr().is_terminal(),
+            last_display: Mutex::new(now),
+            events: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            streams: Mutex::new(HashSet::new()),
+            streams_done: AtomicUsize::new(0),
+            skipped_events: AtomicUsize::new(0),
+            skipped_streams: AtomicUsize::new(0),
+        }
+    }
+
+    /// Accounts for what a previous run already copied.
+    pub fn skip(&self, events: usize, streams: usize) {
+        self.skipped_events.fetch_add(events, Ordering::Relaxed);
+        self.skipped_streams.fetch_add(streams, Ordering::Relaxed);
+    }
+
+    pub fn record(&self, stream: &str, records: &[eventstore::RecordedEvent]) {
+        let bytes: usize = records
+            .iter()
+            .map(|record| record.data.len() + record.metadata.len())
+            .sum();
+
+        self.events.fetch_add(records.len(), Ordering::Relaxed);
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+        self.streams.lock().unwrap().insert(stream.to_owned());
+        self.tick();
+    }
+
+    pub fn stream_done(&self) {
+        self.streams_done.fetch_add(1, Ordering::Relaxed);
+        self.tick();
+    }
+
+    fn eta(&self, elapsed: Duration) -> Option<Duration> {
+        let (total, done, skipped) = match self.total.as_ref()? {
+            Total::Events(total) => (
+                *total,
+                self.events.load(Ordering::Relaxed),
+                self.skipped_events.load(Ordering::Relaxed),
+            ),
+
+            Total::Streams(total) => (
+                *total,
+                self.streams_done.load(Ordering::Relaxed),
+                self.skipped_streams.load(Ordering::Relaxed),
+            ),
+        };
+
+        let speed = rate(done, elapsed);
+
+        if speed <= 0.0 {
+            return None;
+        }
+
+        let remaining = total.saturating_sub(done + skipped);
+
+        Some(Duration::from_secs_f64(remaining as f64 / speed))
+    }
+
+    /// Refreshes the progress line, at most every second on a terminal and
+    /// every 10 seconds otherwise.
+    fn tick(&self) {
+        let every = if self.interactive {
+            Duration::from_secs(1)
+        } else {
+            Duration::from_secs(10)
+        };
+
+        {
+            let mut last_display = self.last_display.lock().unwrap();
+
+            if last_display.elapsed() < every {
+                return;
+            }
+
+            *last_display = Instant::now();
+        }
+
+        let elapsed = self.started.elapsed();
+        let events = self.events.load(Ordering::Relaxed);
+        let mut line = format!(
+            "Copied {} events ({}) from {} streams, {:.0} events/sec",
+            events,
+            format_bytes(self.bytes.load(Ordering::Relaxed)),
+            self.streams.lock().unwrap().len(),
+            rate(events, elapsed)
+        );
+
+        if let Some(eta) = self.eta(elapsed) {
+            line.push_str(&format!(", ETA {}", format_duration(eta)));
+        }
+
+        if self.interactive {
+            eprint!("{}{}", CLEAR_LINE, line);
+            let _ = std::io::stderr().flush();
+        } else {
+            eprintln!("{}", line);
+        }
+    }
+
+    pub fn report(&self) -> Report {
+        let elapsed = self.started.elapsed();
+        let events = self.events.load(Ordering::Relaxed);
+
+        Report {
+            source: self.source.clone(),
+            destination: self.destination.clone(),
+            resumed: self.resumed,
+            events,
+            streams: self.streams.lock().unwrap().len(),
+            bytes: self.bytes.load(Ordering::Relaxed),
+            skipped_events: self.skipped_events.load(Ordering::Relaxed),
+            skipped_streams: self.skipped_streams.load(Ordering::Relaxed),
+            elapsed_secs: elapsed.as_secs_f64(),
+            events_per_sec: rate(events, elapsed),
+        }
+    }
+
+    pub fn print_report(&self, format: OutputFormat) -> CerberusResult<()> {
+        if self.interactive {
+            eprint!("{}", CLEAR_LINE);
+        }
+
+        let report = self.report();
+
+        if format == OutputFormat::Json {
+            return crate::common::print_json(&report);
+        }
+
+        println!(
+            "Export of [{}] to {} done.",
+            report.source, report.destination
+        );
+
+        if report.resumed {
+            println!(
+                "Resumed from checkpoint: skipped {} events and {} streams already copied.",
+                report.skipped_events, report.skipped_streams
+            );
+        }
+
+        println!(
+            "Copied {} events ({}) from {} streams in {}, {:.0} events/sec.",
+            report.events,
+            format_bytes(report.bytes),
+            report.streams,
+            format_duration(Duration::from_secs_f64(report.elapsed_secs)),
+            report.events_per_sec
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,211 @@
+use super::archive::{self, ArchivedEvent, Compression};
+use crate::common::{record_to_event_data, CerberusResult};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Stream files a directory export keeps opened at the same time. Past that,
+/// the least recently written one is closed, and reopened if needed.
+const MAX_OPEN_FILES: usize = 64;
+
+/// Stream files of a directory export.
+pub struct StreamFiles {
+    writers: HashMap<String, (u64, archive::Writer)>,
+
+    /// Length to reopen files at, by file name. Files missing from it are
+    /// truncated when first opened.
+    lengths: HashMap<String, u64>,
+    clock: u64,
+}
+
+impl StreamFiles {
+    /// When resuming, `lengths` are the ones recorded by the checkpoint.
+    pub fn new(lengths: HashMap<String, u64>) -> Self {
+        StreamFiles {
+            writers: HashMap::new(),
+            lengths,
+            clock: 0,
+        }
+    }
+
+    fn writer(
+        &mut self,
+        root: &std::path::Path,
+        compression: Compression,
+        stream: &str,
+    ) -> CerberusResult<&mut archive::Writer> {
+        self.clock += 1;
+
+        if !self.writers.contains_key(stream) {
+            if self.writers.len() >= MAX_OPEN_FILES {
+                self.close_least_recent(compression)?;
+            }
+
+            let name = archive::stream_file_name(stream, compression);
+            let length = self.lengths.get(&name).copied();
+            let writer = archive::Writer::open(&root.join(name), compression, length)?;
+
+            self.writers.insert(stream.to_owned(), (self.clock, writer));
+        }
+
+        let (last_used, writer) = self.writers.get_mut(stream).unwrap();
+
+        *last_used = self.clock;
+
+        Ok(writer)
+    }
+
+    fn close_least_recent(&mut self, compression: Compression) -> CerberusResult<()> {
+        let stream = self
+            .writers
+            .iter()
+            .min_by_key(|(_, (last_used, _))| *last_used)
+            .map(|(stream, _)| stream.clone());
+
+        match stream {
+            Some(stream) => self.close(&stream, compression),
+            None => Ok(()),
+        }
+    }
+
+    fn close(&mut self, stream: &str, compression: Compression) -> CerberusResult<()> {
+        if let Some((_, writer)) = self.writers.remove(stream) {
+            let length = writer.finish()?;
+
+            self.lengths
+                .insert(archive::stream_file_name(stream, compression), length);
+        }
+
+        Ok(())
+    }
+
+    fn finish(self) -> CerberusResult<()> {
+        for (_, (_, writer)) in self.writers {
+            writer.finish()?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Where exported events end up. A sink is shared by streams copied
+/// concurrently, events of different streams can interleave in a file but
+/// stay ordered within each stream.
+pub enum Sink {
+    Database {
+        connection: eventstore::Connection,
+        endpoint: String,
+    },
+    File {
+        path: PathBuf,
+        name: String,
+        writer: Mutex<archive::Writer>,
+
+        /// Syncs the file after every write, when progress is checkpointed.
+        durable: bool,
+    },
+    Directory {
+        root: PathBuf,
+        compression: Compression,
+        files: Mutex<StreamFiles>,
+
+        /// Syncs files after every write, when progress is checkpointed.
+        durable: bool,
+    },
+}
+
+impl Sink {
+    /// Writes events that all belong to the given stream, in order, then
+    /// calls `checkpoint` with the name and length of the archive file they
+    /// went to, if any. It's called while the file is still locked, so the
+    /// recorded length never covers events from a write not recorded yet.
+    pub async fn write<F>(
+        &self,
+        stream: &str,
+        records: &[eventstore::RecordedEvent],
+        checkpoint: F,
+    ) -> CerberusResult<()>
+    where
+        F: FnOnce(Option<(&str, u64)>) -> CerberusResult<()>,
+    {
+        match self {
+            Sink::Database { connection, .. } => {
+                connection
+                    .write_events(stream)
+                    .append_events(records.iter().map(record_to_event_data))
+                    .execute()
+                    .await?;
+
+                checkpoint(None)
+            }
+
+            Sink::File {
+                name,
+                writer,
+                durable,
+                ..
+            } => {
+                let mut writer = writer.lock().unwrap();
+
+                for record in records {
+                    writer.write(&ArchivedEvent::from_record(record))?;
+                }
+
+                if *durable {
+                    checkpoint(Some((name, writer.commit()?)))
+                } else {
+                    checkpoint(None)
+                }
+            }
+
+            Sink::Directory {
+                root,
+                compression,
+                files,
+                durable,
+            } => {
+                let mut files = files.lock().unwrap();
+                let writer = files.writer(root, *compression, stream)?;
+
+                for record in records {
+                    writer.write(&ArchivedEvent::from_record(record))?;
+                }
+
+                if *durable {
+                    let name = archive::stream_file_name(stream, *compression);
+
+                    checkpoint(Some((&name, writer.commit()?)))
+                } else {
+                    checkpoint(None)
+                }
+            }
+        }
+    }
+
+    /// Tells no more event will be written to `stream`, so its file can be
+    /// closed right away.
+    pub fn close(&self, stream: &str) -> CerberusResult<()> {
+        match self {
+            Sink::Directory {
+                files, compression, ..
+            } => files.lock().unwrap().close(stream, *compression),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Sink::Database { endpoint, .. } => format!("[{}] database", endpoint),
+            Sink::File { path, .. } => format!("[{}] file", path.display()),
+            Sink::Directory { root, .. } => format!("[{}] directory", root.display()),
+        }
+    }
+
+    pub fn finish(self) -> CerberusResult<()> {
+        match self {
+            Sink::File { writer, .. } => writer.into_inner().unwrap().finish().map(|_| ()),
+            Sink::Directory { files, .. } => files.into_inner().unwrap().finish(),
+            _ => Ok(()),
+        }
+    }
+}
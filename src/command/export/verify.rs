@@ -0,0 +1,596 @@
+//! Compares exported streams between the source and the destination, once
+//! an export is done.
+use super::archive::{self, ArchivedEvent};
+use super::{Limit, Selection};
+use crate::common::{CerberusError, CerberusResult, OutputFormat};
+use eventstore::{OperationError, ResolvedEvent};
+use futures::stream::Stream;
+use futures::{StreamExt, TryStreamExt};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Where the events were exported to.
+pub enum Destination {
+    Database(eventstore::Connection),
+    File(PathBuf),
+    Directory(PathBuf),
+}
+
+struct Digest {
+    number: i64,
+    id: String,
+    event_type: String,
+    hash: u64,
+}
+
+/// JSON payloads are hashed in a canonical form, as archives don't keep the
+/// original formatting.
+fn hash_payload(hasher: &mut DefaultHasher, payload: &[u8]) {
+    match serde_json::from_slice::<serde_json::Value>(payload) {
+        Ok(value) if !payload.is_empty() => value.to_string().hash(hasher),
+        _ => payload.hash(hasher),
+    }
+}
+
+fn payload_hash(data: &[u8], metadata: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    hash_payload(&mut hasher, data);
+    hash_payload(&mut hasher, metadata);
+
+    hasher.finish()
+}
+
+impl Digest {
+    fn from_record(record: &eventstore::RecordedEvent) -> Digest {
+        Digest {
+            number: record.event_number,
+            id: record.event_id.to_string(),
+            event_type: record.event_type.clone(),
+            hash: payload_hash(&record.data, &record.metadata),
+        }
+    }
+
+    fn from_archived(event: &ArchivedEvent) -> CerberusResult<Digest> {
+        let (data, metadata) = event.payloads()?;
+
+        Ok(Digest {
+            number: event.number,
+            id: event.id.clone(),
+            event_type: event.event_type.clone(),
+            hash: payload_hash(&data, &metadata),
+        })
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EventPosition {
+    id: String,
+    number: i64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Mismatch {
+    id: String,
+    source_number: i64,
+    destination_number: i64,
+    reason: &'static str,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamReport {
+    stream: String,
+    source_events: usize,
+    destination_events: usize,
+    missing: Vec<EventPosition>,
+    extra: Vec<EventPosition>,
+    mismatched: Vec<Mismatch>,
+}
+
+impl StreamReport {
+    fn is_ok(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Report {
+    streams: usize,
+    events: usize,
+    differing_streams: Vec<StreamReport>,
+}
+
+/// Pairs events by id. Positions are reported on both sides since they
+/// don't have to match, like when the destination stream already had events.
+fn compare(stream: &str, source: &[Digest], destination: &[Digest]) -> StreamReport {
+    let by_id: HashMap<&str, &Digest> = destination
+        .iter()
+        .map(|digest| (digest.id.as_str(), digest))
+        .collect();
+
+    let mut report = StreamReport {
+        stream: stream.to_owned(),
+        source_events: source.len(),
+        destination_events: destination.len(),
+        missing: Vec::new(),
+        extra: Vec::new(),
+        mismatched: Vec::new(),
+    };
+
+    for expected in source {
+        let actual = match by_id.get(expected.id.as_str()) {
+            Some(actual) => actual,
+            None => {
+                report.missing.push(EventPosition {
+                    id: expected.id.clone(),
+                    number: expected.number,
+                });
+
+                continue;
+            }
+        };
+
+        let reason = if expected.event_type != actual.event_type {
+            "type"
+        } else if expected.hash != actual.hash {
+            "payload"
+        } else {
+            continue;
+        };
+
+        report.mismatched.push(Mismatch {
+            id: expected.id.clone(),
+            source_number: expected.number,
+            destination_number: actual.number,
+            reason,
+        });
+    }
+
+    let source_ids: HashMap<&str, ()> = source
+        .iter()
+        .map(|digest| (digest.id.as_str(), ()))
+        .collect();
+
+    for actual in destination {
+        if !source_ids.contains_key(actual.id.as_str()) {
+            report.extra.push(EventPosition {
+                id: actual.id.clone(),
+                number: actual.number,
+            });
+        }
+    }
+
+    report
+}
+
+/// Reads a whole stream. A deleted stream is considered empty.
+async fn read_digests(
+    connection: &eventstore::Connection,
+    stream: &str,
+    type_filter: Option<&str>,
+) -> CerberusResult<Vec<Digest>> {
+    let mut events = connection.read_stream(stream).iterate_over();
+    let mut digests = Vec::new();
+
+    loop {
+        let event = match events.try_next().await {
+            Ok(Some(event)) => event,
+            Ok(None) | Err(OperationError::StreamDeleted(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        let record = event.get_original_event();
+
+        if type_filter.is_none_or(|tpe| tpe == record.event_type) {
+            digests.push(Digest::from_record(record));
+        }
+    }
+
+    Ok(digests)
+}
+
+fn load_archive(
+    path: &Path,
+    type_filter: Option<&str>,
+    index: &mut HashMap<String, Vec<Digest>>,
+) -> CerberusResult<()> {
+    for event in archive::Reader::open(path)? {
+        let event = event?;
+
+        if type_filter.is_none_or(|tpe| tpe == event.event_type) {
+            let digest = Digest::from_archived(&event)?;
+
+            index.entry(event.stream).or_default().push(digest);
+        }
+    }
+
+    Ok(())
+}
+
+/// Archived events are loaded once, grouped by stream. A database is read
+/// stream by stream.
+enum Index {
+    Database(eventstore::Connection),
+    Archive(HashMap<String, Vec<Digest>>),
+}
+
+impl Index {
+    fn load(destination: Destination, type_filter: Option<&str>) -> CerberusResult<Index> {
+        let mut index = HashMap::new();
+
+        match destination {
+            Destination::Database(connection) => return Ok(Index::Database(connection)),
+            Destination::File(path) => load_archive(&path, type_filter, &mut index)?,
+            Destination::Directory(root) => {
+                for entry in std::fs::read_dir(&root)? {
+                    let path = entry?.path();
+
+                    // Skips anything else living there, like a checkpoint file.
+                    if path.is_file() && archive::is_archive_file(&path) {
+                        load_archive(&path, type_filter, &mut index)?;
+                    }
+                }
+            }
+        }
+
+        Ok(Index::Archive(index))
+    }
+
+    async fn digests(
+        &mut self,
+        stream: &str,
+        type_filter: Option<&str>,
+    ) -> CerberusResult<Vec<Digest>> {
+        match self {
+            Index::Database(connection) => read_digests(connection, stream, type_filter).await,
+            Index::Archive(index) => Ok(index.remove(stream).unwrap_or_default()),
+        }
+    }
+
+    /// Destination streams that aren't part of the source. A database only
+    /// knows about them through its own category or event type stream, so
+    /// only when its system projections run, and they are skipped when
+    /// verifying the latest entries only, as older ones are expected there.
+    async fn leftovers(
+        self,
+        selection: &Selection<'_>,
+        source_stream: &str,
+        sources: &BTreeMap<String, Vec<Digest>>,
+        limit: &Limit,
+    ) -> CerberusResult<Vec<(String, Vec<Digest>)>> {
+        let connection = match (self, limit) {
+            (Index::Archive(index), _) => return Ok(index.into_iter().collect()),
+            (Index::Database(_), Limit::Top(_)) => return Ok(Vec::new()),
+            (Index::Database(connection), Limit::None) => connection,
+        };
+
+        let type_filter = match *selection {
+            Selection::Stream(_) => return Ok(Vec::new()),
+            Selection::EventType(tpe) => Some(tpe),
+            Selection::StreamCategory(_) => None,
+        };
+
+        let mut leftovers = Vec::new();
+
+        for stream in listed_streams(&connection, selection, source_stream).await? {
+            if !sources.contains_key(&stream) {
+                let digests = read_digests(&connection, &stream, type_filter).await?;
+
+                leftovers.push((stream, digests));
+            }
+        }
+
+        Ok(leftovers)
+    }
+}
+
+/// Reads the stream an export selects events from, only its latest entries
+/// when limited, like the export does.
+fn read_source(
+    connection: &eventstore::Connection,
+    source_stream: &str,
+    limit: &Limit,
+) -> Box<dyn Stream<Item = Result<ResolvedEvent, OperationError>> + Unpin> {
+    let command = connection
+        .read_stream(source_stream)
+        .resolve_link_tos(eventstore::LinkTos::ResolveLink);
+
+    match *limit {
+        Limit::Top(top) => Box::new(command.start_from_end_of_stream().iterate_over().take(top)),
+        Limit::None => Box::new(command.iterate_over()),
+    }
+}
+
+/// Streams listed by a category or event type stream. A missing or deleted
+/// one lists nothing.
+async fn listed_streams(
+    connection: &eventstore::Connection,
+    selection: &Selection<'_>,
+    source_stream: &str,
+) -> CerberusResult<BTreeSet<String>> {
+    let mut events = read_source(connection, source_stream, &Limit::None);
+    let mut streams = BTreeSet::new();
+
+    loop {
+        let record = match events.try_next().await {
+            Ok(Some(event)) => match event.event {
+                Some(record) => record,
+                None => continue,
+            },
+            Ok(None) | Err(OperationError::StreamDeleted(_)) => break,
+            Err(e) => return Err(e.into()),
+        };
+
+        match *selection {
+            Selection::StreamCategory(_) => {
+                streams.insert(String::from_utf8_lossy(&record.data).into_owned())
+            }
+            _ => streams.insert(record.event_stream_id),
+        };
+    }
+
+    Ok(streams)
+}
+
+/// Source events, grouped by stream.
+async fn source_digests(
+    connection: &eventstore::Connection,
+    selection: &Selection<'_>,
+    source_stream: &str,
+    limit: &Limit,
+) -> CerberusResult<BTreeMap<String, Vec<Digest>>> {
+    let mut streams = BTreeMap::new();
+
+    match *selection {
+        Selection::Stream(stream) => {
+            let digests = match *limit {
+                Limit::None => read_digests(connection, stream, None).await?,
+                Limit::Top(_) => {
+                    let mut digests: Vec<_> = read_source(connection, stream, limit)
+                        .map_ok(|event| Digest::from_record(event.get_original_event()))
+                        .try_collect()
+                        .await?;
+
+                    digests.reverse();
+                    digests
+                }
+            };
+
+            streams.insert(stream.to_owned(), digests);
+        }
+
+        Selection::StreamCategory(_) => {
+            let mut events = read_source(connection, source_stream, limit);
+
+            while let Some(event) = events.try_next().await? {
+                if let Some(record) = event.event {
+                    let stream = String::from_utf8_lossy(&record.data).into_owned();
+                    let digests = read_digests(connection, &stream, None).await?;
+
+                    streams.insert(stream, digests);
+                }
+            }
+        }
+
+        Selection::EventType(_) => {
+            let mut events = read_source(connection, source_stream, limit);
+
+            while let Some(event) = events.try_next().await? {
+                if let Some(record) = event.event {
+                    streams
+                        .entry(record.event_stream_id.clone())
+                        .or_insert_with(Vec::new)
+                        .push(Digest::from_record(&record));
+                }
+            }
+        }
+    }
+
+    Ok(streams)
+}
+
+fn print_stream_report(report: &StreamReport) {
+    println!(
+        "Stream [{}]: {} source events, {} destination events",
+        report.stream, report.source_events, report.destination_events
+    );
+
+    for event in report.missing.iter() {
+        println!(
+            "\tmissing event {} at source position {}",
+            event.id, event.number
+        );
+    }
+
+    for event in report.extra.iter() {
+        println!(
+            "\textra event {} at destination position {}",
+            event.id, event.number
+        );
+    }
+
+    for event in report.mismatched.iter() {
+        println!(
+            "\tevent {} has a different {} (source position {}, destination position {})",
+            event.id, event.reason, event.source_number, event.destination_number
+        );
+    }
+}
+
+pub async fn run(
+    source_connection: &eventstore::Connection,
+    selection: &Selection<'_>,
+    source_stream: &str,
+    limit: &Limit,
+    destination: Destination,
+    format: OutputFormat,
+) -> CerberusResult<()> {
+    let type_filter = match *selection {
+        Selection::EventType(tpe) => Some(tpe),
+        _ => None,
+    };
+
+    let mut index = Index::load(destination, type_filter)?;
+    let sources = source_digests(source_connection, selection, source_stream, limit).await?;
+    let mut report = Report {
+        streams: 0,
+        events: 0,
+        differing_streams: Vec::new(),
+    };
+
+    for (stream, source) in sources.iter() {
+        let destination = index.digests(stream, type_filter).await?;
+        let stream_report = compare(stream, source, &destination);
+
+        report.streams += 1;
+        report.events += source.len();
+
+        if !stream_report.is_ok() {
+            report.differing_streams.push(stream_report);
+        }
+    }
+
+    let leftovers = index
+        .leftovers(selection, source_stream, &sources, limit)
+        .await?;
+
+    for (stream, destination) in leftovers {
+        report
+            .differing_streams
+            .push(compare(&stream, &[], &destination));
+    }
+
+    report
+        .differing_streams
+        .sort_by(|a, b| a.stream.cmp(&b.stream));
+
+    if format == OutputFormat::Json {
+        crate::common::print_json(&report)?;
+    } else {
+        for stream_report in report.differing_streams.iter() {
+            print_stream_report(stream_report);
+        }
+
+        println!(
+            "Verified {} streams and {} events.",
+            report.streams, report.events
+        );
+    }
+
+    if !report.differing_streams.is_empty() {
+        return Err(CerberusError::user_fault(format!(
+            "{} streams differ between the source and the destination",
+            report.differing_streams.len()
+        )));
+    }
+
+    if format == OutputFormat::Text {
+        println!("Source and destination match.");
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digest(number: i64, id: &str, event_type: &str, data: &str) -> Digest {
+        Digest {
+            number,
+            id: id.to_owned(),
+            event_type: event_type.to_owned(),
+            hash: payload_hash(data.as_bytes(), b""),
+        }
+    }
+
+    #[test]
+    fn matching_streams_pair_events_by_id() {
+        let source = vec![
+            digest(0, "a", "OrderPlaced", "{}"),
+            digest(1, "b", "OrderShipped", "{}"),
+        ];
+        // The destination stream already held an event before the export.
+        let destination = vec![
+            digest(3, "a", "OrderPlaced", "{}"),
+            digest(4, "b", "OrderShipped", "{}"),
+        ];
+
+        let report = compare("orders-1", &source, &destination);
+
+        assert!(report.is_ok());
+        assert_eq!(report.source_events, 2);
+        assert_eq!(report.destination_events, 2);
+    }
+
+    #[test]
+    fn reports_missing_and_extra_events() {
+        let source = vec![digest(0, "a", "OrderPlaced", "{}")];
+        let destination = vec![digest(0, "b", "OrderPlaced", "{}")];
+
+        let report = compare("orders-1", &source, &destination);
+
+        assert!(!report.is_ok());
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].id, "a");
+        assert_eq!(report.extra.len(), 1);
+        assert_eq!(report.extra[0].id, "b");
+        assert!(report.mismatched.is_empty());
+    }
+
+    #[test]
+    fn reports_mismatched_types_and_payloads() {
+        let source = vec![
+            digest(0, "a", "OrderPlaced", r#"{"total":12}"#),
+            digest(1, "b", "OrderPlaced", r#"{"total":12}"#),
+        ];
+        let destination = vec![
+            digest(0, "a", "OrderShipped", r#"{"total":12}"#),
+            digest(2, "b", "OrderPlaced", r#"{"total":13}"#),
+        ];
+
+        let report = compare("orders-1", &source, &destination);
+        let reasons: Vec<_> = report
+            .mismatched
+            .iter()
+            .map(|mismatch| (mismatch.id.as_str(), mismatch.reason))
+            .collect();
+
+        assert_eq!(reasons, vec![("a", "type"), ("b", "payload")]);
+        assert_eq!(report.mismatched[1].source_number, 1);
+        assert_eq!(report.mismatched[1].destination_number, 2);
+        assert!(report.missing.is_empty());
+        assert!(report.extra.is_empty());
+    }
+
+    #[test]
+    fn hashes_json_payloads_in_canonical_form() {
+        let compact = payload_hash(br#"{"a":1,"b":[true,null]}"#, b"");
+        let formatted = payload_hash(b"{ \"b\": [true, null],\n  \"a\": 1 }", b"");
+
+        assert_eq!(compact, formatted);
+        assert_ne!(compact, payload_hash(br#"{"a":2,"b":[true,null]}"#, b""));
+
+        // Not JSON, so compared byte for byte.
+        assert_ne!(payload_hash(b"a b", b""), payload_hash(b"a  b", b""));
+        assert_ne!(payload_hash(b"{}", b""), payload_hash(b"", b"{}"));
+    }
+
+    #[test]
+    fn only_loads_archive_files() {
+        assert!(archive::is_archive_file(Path::new("out/orders-1.jsonl")));
+        assert!(archive::is_archive_file(Path::new("out/orders-1.jsonl.gz")));
+        assert!(archive::is_archive_file(Path::new(
+            "out/orders-1.jsonl.zst"
+        )));
+        assert!(!archive::is_archive_file(Path::new("out/state.json")));
+        assert!(!archive::is_archive_file(Path::new("out/state.tmp")));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::command::export::archive::{ArchivedEvent, Reader};
+use crate::command::export::DEFAULT_BUFFER_SIZE;
+use crate::common::{CerberusError, CerberusResult};
+use eventstore::ExpectedVersion;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+enum VersionCheck {
+    /// No check, the server still deduplicates events by id.
+    Any,
+
+    /// Streams must not exist on the destination before the import.
+    NoStream,
+
+    /// Every event must land at the same number it had in the archive.
+    Archive,
+}
+
+/// Stream name remapping, `orders-*=old-orders-*` renames by prefix.
+struct Mapping {
+    from: String,
+    to: String,
+    prefix: bool,
+}
+
+impl Mapping {
+    fn parse(param: &str) -> CerberusResult<Mapping> {
+        let mut parts = param.splitn(2, '=');
+        let from = parts.next().unwrap_or_default();
+        let to = match parts.next() {
+            Some(to) if !from.is_empty() && !to.is_empty() => to,
+            _ => {
+                return Err(CerberusError::user_fault(format!(
+                    "Invalid --map value [{}], expected FROM=TO",
+                    param
+                )));
+            }
+        };
+
+        let from_prefix = from.strip_suffix('*');
+        let to_prefix = to.strip_suffix('*');
+
+        match (from_prefix, to_prefix) {
+            (Some(from), Some(to)) => Ok(Mapping {
+                from: from.to_owned(),
+                to: to.to_owned(),
+                prefix: true,
+            }),
+
+            (None, None) => Ok(Mapping {
+                from: from.to_owned(),
+                to: to.to_owned(),
+                prefix: false,
+            }),
+
+            _ => Err(CerberusError::user_fault(format!(
+                "Invalid --map value [{}], both sides must end with * to rename by prefix",
+                param
+            ))),
+        }
+    }
+
+    fn apply(&self, stream: &str) -> Option<String> {
+        if self.prefix {
+            stream
+                .strip_prefix(self.from.as_str())
+                .map(|rest| format!("{}{}", self.to, rest))
+        } else if stream == self.from {
+            Some(self.to.clone())
+        } else {
+            None
+        }
+    }
+}
+
+fn map_stream(mappings: &[Mapping], stream: &str) -> String {
+    mappings
+        .iter()
+        .find_map(|mapping| mapping.apply(stream))
+        .unwrap_or_else(|| stream.to_owned())
+}
+
+/// Expected versions of the destination streams, following the version check.
+struct Versions {
+    check: VersionCheck,
+
+    /// Next expected version of the streams written so far.
+    next: HashMap<String, i64>,
+
+    /// Source stream and number of the last archived event read for every
+    /// destination stream, used by the archive version check.
+    archived: HashMap<String, (String, i64)>,
+}
+
+impl Versions {
+    fn new(check: VersionCheck) -> Self {
+        Versions {
+            check,
+            next: HashMap::new(),
+            archived: HashMap::new(),
+        }
+    }
+
+    /// Expected versions taken from the archive only make sense when every
+    /// destination stream gets a gap-free run of a single source stream.
+    /// Exports by event type, or mappings merging streams, don't give that.
+    fn check_contiguous(&mut self, stream: &str, event: &ArchivedEvent) -> CerberusResult<()> {
+        if let VersionCheck::Archive = self.check {
+            if let Some((source, number)) = self.archived.get(stream) {
+                if *source != event.stream {
+                    return Err(CerberusError::user_fault(format!(
+                        "Both [{}] and [{}] archived streams are imported into [{}]. \
+                        --expected-version archive can't be used when streams are merged",
+                        source, event.stream, stream
+                    )));
+                }
+
+                if event.number != number + 1 {
+                    return Err(CerberusError::user_fault(format!(
+                        "Archived stream [{}] jumps from event {} to event {}. \
+                        --expected-version archive needs whole streams, like exports \
+                        by stream or by category, not by event type",
+                        event.stream, number, event.number
+                    )));
+                }
+            }
+
+            self.archived
+                .insert(stream.to_owned(), (event.stream.clone(), event.number));
+        }
+
+        Ok(())
+    }
+
+    fn expected_version(&self, stream: &str, first: &ArchivedEvent) -> ExpectedVersion {
+        match self.check {
+            VersionCheck::Any => ExpectedVersion::Any,
+
+            VersionCheck::NoStream => match self.next.get(stream) {
+                Some(version) => ExpectedVersion::Exact(*version),
+                None => ExpectedVersion::NoStream,
+            },
+
+            VersionCheck::Archive if first.number == 0 => ExpectedVersion::NoStream,
+            VersionCheck::Archive => ExpectedVersion::Exact(first.number - 1),
+        }
+    }
+
+    fn record(&mut self, stream: &str, next_expected_version: i64) {
+        self.next.insert(stream.to_owned(), next_expected_version);
+    }
+}
+
+struct Importer<'a> {
+    connection: &'a eventstore::Connection,
+    versions: Versions,
+    streams: HashSet<String>,
+    imported: usize,
+}
+
+impl<'a> Importer<'a> {
+    async fn flush(&mut self, stream: &str, batch: &mut Vec<ArchivedEvent>) -> CerberusResult<()> {
+        if batch.is_empty() {
+            return Ok(());
+        }
+
+        let version = self.versions.expected_version(stream, &batch[0]);
+        let mut events = Vec::with_capacity(batch.len());
+
+        for event in batch.iter() {
+            events.push(event.to_event_data()?);
+        }
+
+        let result = self
+            .connection
+            .write_events(stream)
+            .append_events(events)
+            .expected_version(version)
+            .execute()
+            .await
+            .map_err(|e| match e {
+                eventstore::OperationError::WrongExpectedVersion(_, version) => {
+                    CerberusError::user_fault(format!(
+                        "Stream [{}] isn't at the expected version {:?} when importing \
+                        archived event number {}",
+                        stream, version, batch[0].number
+                    ))
+                }
+
+                e => CerberusError::user_fault(format!(
+                    "Failed to write to [{}] stream: {}",
+                    stream, e
+                )),
+            })?;
+
+        info!("Imported {} events into {}", batch.len(), stream);
+
+        self.versions.record(stream, result.next_expected_version);
+        self.streams.insert(stream.to_owned());
+        self.imported += batch.len();
+        batch.clear();
+
+        Ok(())
+    }
+}
+
+pub async fn run(
+    global: &clap::ArgMatches<'_>,
+    params: &clap::ArgMatches<'_>,
+) -> CerberusResult<()> {
+    let path = params
+        .value_of("from-file")
+        .expect("Already checked by Clap");
+
+    let check = match params.value_of("expected-version") {
+        Some("no-stream") => VersionCheck::NoStream,
+        Some("archive") => VersionCheck::Archive,
+        _ => VersionCheck::Any,
+    };
+
+    let mut mappings = Vec::new();
+
+    for param in params.values_of("map").into_iter().flatten() {
+        mappings.push(Mapping::parse(param)?);
+    }
+
+    let connection = crate::common::create_connection_default(global).await?;
+    let mut importer = Importer {
+        connection: &connection,
+        versions: Versions::new(check),
+        streams: HashSet::new(),
+        imported: 0,
+    };
+
+    // Consecutive events of the same stream are written together, which
+    // keeps the archive order within every stream.
+    let mut batch = Vec::with_capacity(DEFAULT_BUFFER_SIZE);
+    let mut stream_name = String::new();
+
+    for event in Reader::open(Path::new(path))? {
+        let event = event?;
+        let target = map_stream(&mappings, &event.stream);
+
+        importer.versions.check_contiguous(&target, &event)?;
+
+        if target != stream_name || batch.len() == DEFAULT_BUFFER_SIZE {
+            importer.flush(&stream_name, &mut batch).await?;
+            stream_name = target;
+        }
+
+        batch.push(event);
+    }
+
+    importer.flush(&stream_name, &mut batch).await?;
+
+    println!(
+        "{} events imported into {} streams.",
+        importer.imported,
+        importer.streams.len()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archived(stream: &str, number: i64) -> ArchivedEvent {
+        serde_json::from_value(serde_json::json!({
+            "stream": stream,
+            "number": number,
+            "id": uuid::Uuid::new_v4().to_string(),
+            "type": "OrderPlaced",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn mapping_renames_exact_streams() {
+        let mappings = vec![Mapping::parse("orders-1=legacy-orders-1").unwrap()];
+
+        assert_eq!(map_stream(&mappings, "orders-1"), "legacy-orders-1");
+        assert_eq!(map_stream(&mappings, "orders-10"), "orders-10");
+    }
+
+    #[test]
+    fn mapping_renames_by_prefix() {
+        let mappings = vec![
+            Mapping::parse("orders-*=legacy-orders-*").unwrap(),
+            Mapping::parse("orders-1=unused").unwrap(),
+        ];
+
+        assert_eq!(map_stream(&mappings, "orders-1"), "legacy-orders-1");
+        assert_eq!(map_stream(&mappings, "customers-1"), "customers-1");
+    }
+
+    #[test]
+    fn mapping_rejects_invalid_values() {
+        assert!(Mapping::parse("orders").is_err());
+        assert!(Mapping::parse("=orders").is_err());
+        assert!(Mapping::parse("orders=").is_err());
+        assert!(Mapping::parse("orders-*=legacy").is_err());
+    }
+
+    fn check_contiguous(check: VersionCheck, events: &[(&str, &str, i64)]) -> bool {
+        let mut versions = Versions::new(check);
+
+        events.iter().all(|(target, stream, number)| {
+            versions
+                .check_contiguous(target, &archived(stream, *number))
+                .is_ok()
+        })
+    }
+
+    #[test]
+    fn expected_versions_follow_the_check() {
+        let archive = Versions::new(VersionCheck::Archive);
+
+        assert!(matches!(
+            archive.expected_version("a", &archived("a", 0)),
+            ExpectedVersion::NoStream
+        ));
+        assert!(matches!(
+            archive.expected_version("a", &archived("a", 5)),
+            ExpectedVersion::Exact(4)
+        ));
+
+        let mut no_stream = Versions::new(VersionCheck::NoStream);
+
+        assert!(matches!(
+            no_stream.expected_version("a", &archived("a", 5)),
+            ExpectedVersion::NoStream
+        ));
+
+        no_stream.record("a", 2);
+
+        assert!(matches!(
+            no_stream.expected_version("a", &archived("a", 5)),
+            ExpectedVersion::Exact(2)
+        ));
+    }
+
+    #[test]
+    fn archive_check_accepts_whole_streams() {
+        let events = [("a", "a", 3), ("b", "b", 0), ("a", "a", 4), ("b", "b", 1)];
+
+        assert!(check_contiguous(VersionCheck::Archive, &events));
+    }
+
+    #[test]
+    fn archive_check_rejects_gaps() {
+        let events = [("a", "a", 0), ("a", "a", 2)];
+
+        assert!(!check_contiguous(VersionCheck::Archive, &events));
+        assert!(check_contiguous(VersionCheck::Any, &events));
+    }
+
+    #[test]
+    fn archive_check_rejects_merged_streams() {
+        let events = [("merged", "a", 0), ("merged", "b", 1)];
+
+        assert!(!check_contiguous(VersionCheck::Archive, &events));
+    }
+}
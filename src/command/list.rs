@@ -3,18 +3,19 @@ pub mod events {
     use eventstore::{OperationError, ResolvedEvent};
     use futures::stream::{Stream, StreamExt, TryStreamExt};
 
+    #[allow(clippy::unnecessary_to_owned)]
     fn get_stream_name(params: &clap::ArgMatches) -> CerberusResult<String> {
         if let Some(original_stream_name) = params.value_of("stream") {
             if let Some(group_id) = params.value_of("group-id") {
                 if params.is_present("checkpoint") {
-                    Ok(format!(
-                        "$persistentsubscription-{}::{}-checkpoint",
-                        original_stream_name, group_id
+                    Ok(crate::common::checkpoint_stream_name(
+                        original_stream_name,
+                        group_id,
                     ))
                 } else {
-                    Ok(format!(
-                        "$persistentsubscription-{}::{}-parked",
-                        original_stream_name, group_id
+                    Ok(crate::common::parked_stream_name(
+                        original_stream_name,
+                        group_id,
                     ))
                 }
             } else {
@@ -118,6 +119,7 @@ pub mod streams {
         }
     }
 
+    #[allow(clippy::suspicious_to_owned)]
     pub async fn run(
         global: &clap::ArgMatches<'_>,
         params: &clap::ArgMatches<'_>,
@@ -200,23 +202,266 @@ pub mod streams {
 
 pub mod subscriptions {
     use crate::api::Api;
-    use crate::common::CerberusResult;
+    use crate::common::{CerberusError, CerberusResult, SubscriptionSummary};
+    use colored::Colorize;
+    use std::collections::HashMap;
+    use std::time::Duration;
+
+    const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+    /// ANSI sequence clearing the terminal and moving the cursor to the top left corner.
+    const CLEAR_SCREEN: &str = "\x1B[2J\x1B[H";
+
+    fn lag(sub: &SubscriptionSummary) -> i64 {
+        sub.last_known_event_number - sub.last_processed_event_number
+    }
+
+    enum Sort {
+        Lag,
+        Rate,
+        Name,
+    }
+
+    /// Filters and ordering passed on the command line, applied on top of
+    /// the subscriptions returned by the server.
+    struct Filters<'a> {
+        stream: Option<&'a str>,
+        group: Option<&'a str>,
+        status: Option<&'a str>,
+        min_lag: Option<i64>,
+        no_connections: bool,
+        sort: Option<Sort>,
+    }
+
+    impl<'a> Filters<'a> {
+        fn parse(params: &'a clap::ArgMatches) -> CerberusResult<Filters<'a>> {
+            let min_lag = match params.value_of("min-lag") {
+                Some(param) => Some(param.parse().map_err(|e| {
+                    CerberusError::user_fault(format!(
+                        "Failed to parse --min-lag number parameter: {}",
+                        e
+                    ))
+                })?),
+
+                None => None,
+            };
+
+            let sort = match params.value_of("sort") {
+                Some("lag") => Some(Sort::Lag),
+                Some("rate") => Some(Sort::Rate),
+                Some("name") => Some(Sort::Name),
+                Some(wrong) => {
+                    return Err(CerberusError::user_fault(format!(
+                        "Unknown --sort value: [{}]",
+                        wrong
+                    )));
+                }
+                None => None,
+            };
+
+            Ok(Filters {
+                stream: params.value_of("stream"),
+                group: params.value_of("group"),
+                status: params.value_of("status"),
+                min_lag,
+                no_connections: params.is_present("no-connections"),
+                sort,
+            })
+        }
+
+        fn matches(&self, sub: &SubscriptionSummary) -> bool {
+            self.stream
+                .is_none_or(|pattern| crate::common::glob_match(pattern, &sub.event_stream_id))
+                && self
+                    .group
+                    .is_none_or(|pattern| crate::common::glob_match(pattern, &sub.group_name))
+                && self
+                    .status
+                    .is_none_or(|status| status.eq_ignore_ascii_case(&sub.status))
+                && self.min_lag.is_none_or(|min| lag(sub) >= min)
+                && (!self.no_connections || sub.connection_count == 0)
+        }
+
+        /// Keeps the subscriptions matching the filters, in the requested
+        /// order. Lag and rate sort in descending order, so the unhealthiest
+        /// groups come first.
+        fn apply<A, F>(&self, items: Vec<A>, summary: F) -> Vec<A>
+        where
+            F: Fn(&A) -> &SubscriptionSummary,
+        {
+            let mut items: Vec<A> = items
+                .into_iter()
+                .filter(|item| self.matches(summary(item)))
+                .collect();
+
+            match self.sort {
+                Some(Sort::Lag) => items.sort_by_key(|item| std::cmp::Reverse(lag(summary(item)))),
+                Some(Sort::Rate) => items.sort_by(|a, b| {
+                    summary(b)
+                        .average_items_per_sec
+                        .total_cmp(&summary(a).average_items_per_sec)
+                }),
+                Some(Sort::Name) => items.sort_by(|a, b| {
+                    let (a, b) = (summary(a), summary(b));
+
+                    (&a.event_stream_id, &a.group_name).cmp(&(&b.event_stream_id, &b.group_name))
+                }),
+                None => {}
+            }
+
+            items
+        }
+    }
+
+    fn draw(
+        subs: &[SubscriptionSummary],
+        previous_lags: &HashMap<(String, String), i64>,
+        lag_threshold: Option<i64>,
+        interval: Duration,
+    ) {
+        let stream_width = subs
+            .iter()
+            .map(|sub| sub.event_stream_id.len())
+            .chain(std::iter::once("STREAM".len()))
+            .max()
+            .unwrap_or_default();
+
+        let group_width = subs
+            .iter()
+            .map(|sub| sub.group_name.len())
+            .chain(std::iter::once("GROUP".len()))
+            .max()
+            .unwrap_or_default();
+
+        print!("{}", CLEAR_SCREEN);
+        println!(
+            "{:sw$}  {:gw$}  {:12}  {:>5}  {:>21}  {:>8}  {:>9}  {:>10}",
+            "STREAM",
+            "GROUP",
+            "STATUS",
+            "CONN",
+            "PROCESSED/KNOWN",
+            "LAG",
+            "LAG DELTA",
+            "MSGS/SEC",
+            sw = stream_width,
+            gw = group_width
+        );
+
+        for sub in subs {
+            let key = (sub.event_stream_id.clone(), sub.group_name.clone());
+            let lag = lag(sub);
+            let delta = previous_lags
+                .get(&key)
+                .map(|previous| format!("{:+}", lag - previous))
+                .unwrap_or_else(|| "-".to_owned());
+
+            let row = format!(
+                "{:sw$}  {:gw$}  {:12}  {:>5}  {:>21}  {:>8}  {:>9}  {:>10.2}",
+                sub.event_stream_id,
+                sub.group_name,
+                sub.status,
+                sub.connection_count,
+                format!(
+                    "{}/{}",
+                    sub.last_processed_event_number, sub.last_known_event_number
+                ),
+                lag,
+                delta,
+                sub.average_items_per_sec,
+                sw = stream_width,
+                gw = group_width
+            );
+
+            let lagging = lag_threshold.is_some_and(|threshold| lag > threshold);
+
+            if lagging {
+                println!("{}", row.red());
+            } else if sub.connection_count == 0 {
+                println!("{}", row.yellow());
+            } else {
+                println!("{}", row);
+            }
+        }
+
+        println!();
+        println!("Refreshing every {:?}, press Ctrl-C to stop.", interval);
+    }
+
+    async fn watch(
+        params: &clap::ArgMatches<'_>,
+        filters: &Filters<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let interval = match params.value_of("interval") {
+            Some(param) => crate::common::parse_duration("interval", param)?,
+            None => DEFAULT_WATCH_INTERVAL,
+        };
+
+        let lag_threshold = match params.value_of("lag-threshold") {
+            Some(param) => Some(param.parse().map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to parse --lag-threshold number parameter: {}",
+                    e
+                ))
+            })?),
+
+            None => None,
+        };
+
+        let mut previous_lags = HashMap::new();
+
+        loop {
+            let subs = filters.apply(api.subscriptions().await?, |sub| sub);
+
+            draw(&subs, &previous_lags, lag_threshold, interval);
+
+            previous_lags = subs
+                .iter()
+                .map(|sub| {
+                    let key = (sub.event_stream_id.clone(), sub.group_name.clone());
+
+                    (key, lag(sub))
+                })
+                .collect();
+
+            tokio::time::delay_for(interval).await;
+        }
+    }
 
     pub async fn run(
         _: &clap::ArgMatches<'_>,
         params: &clap::ArgMatches<'_>,
         api: Api<'_>,
     ) -> CerberusResult<()> {
+        let filters = Filters::parse(params)?;
+
+        if params.is_present("watch") {
+            return watch(params, &filters, api).await;
+        }
+
         if params.is_present("raw") {
-            let subs = api.subscriptions_raw().await?;
+            let mut subs = Vec::new();
 
-            for sub in subs {
+            for raw in api.subscriptions_raw().await? {
+                let summary: SubscriptionSummary =
+                    serde_json::from_value(raw.clone()).map_err(|e| {
+                        CerberusError::dev_fault(format!(
+                            "Failed to deserialize SubscriptionSummary: {}",
+                            e
+                        ))
+                    })?;
+
+                subs.push((summary, raw));
+            }
+
+            for (_, sub) in filters.apply(subs, |(summary, _)| summary) {
                 println!("--------------------------------------------------------------");
                 serde_json::to_writer_pretty(std::io::stdout(), &sub).unwrap();
                 println!();
             }
         } else {
-            let subs = api.subscriptions().await?;
+            let subs = filters.apply(api.subscriptions().await?, |sub| sub);
 
             for sub in subs {
                 let process_diff = sub.last_known_event_number - sub.last_processed_event_number;
@@ -239,11 +484,92 @@ pub mod subscriptions {
 }
 
 pub mod subscription {
-    use crate::api::Api;
-    use crate::common::CerberusResult;
+    use crate::api::{Api, SubscriptionInfo};
+    use crate::common::{CerberusResult, OutputFormat};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct Detailed {
+        #[serde(flatten)]
+        info: SubscriptionInfo,
+        parked_message_count: i64,
+    }
+
+    /// Latency percentiles come either as an object or as a list of key/value pairs.
+    fn format_extra_statistics(stats: &serde_json::Value) -> String {
+        let pairs: Vec<String> = match stats {
+            serde_json::Value::Object(map) => map
+                .iter()
+                .map(|(key, value)| format!("{}: {}", key, value))
+                .collect(),
+
+            serde_json::Value::Array(entries) => entries
+                .iter()
+                .map(|entry| format!("{}: {}", entry["key"], entry["value"]))
+                .collect(),
+
+            other => vec![other.to_string()],
+        };
+
+        pairs.join(", ")
+    }
+
+    fn print_detailed(detailed: &Detailed) {
+        let info = &detailed.info;
+        let extra_statistics = info
+            .config
+            .as_ref()
+            .is_some_and(|config| config.extra_statistics);
+
+        println!("Stream: {}", info.event_stream_id);
+        println!("Group: {}", info.group_name);
+        println!("Status: {}", info.status);
+        println!(
+            "Processed / Known: {} / {} ({})",
+            info.last_processed_event_number,
+            info.last_known_event_number,
+            info.last_known_event_number - info.last_processed_event_number
+        );
+        println!(
+            "Processing speed: {} msgs/sec, total items: {}",
+            info.average_items_per_second, info.total_items_processed
+        );
+        println!("In-flight messages: {}", info.total_in_flight_messages);
+        println!(
+            "Buffers: read {}, live {}, retry {}",
+            info.read_buffer_count, info.live_buffer_count, info.retry_buffer_count
+        );
+        println!("Parked messages: {}", detailed.parked_message_count);
+        println!("Connections: {}", info.connections.len());
+
+        for conn in info.connections.iter() {
+            println!("--------------------------------------------------------------");
+            println!("From: {}", conn.from);
+            println!("Username: {}", conn.username);
+
+            if let Some(name) = conn.connection_name.as_ref() {
+                println!("Connection name: {}", name);
+            }
+
+            println!(
+                "In-flight messages / Available slots: {} / {}",
+                conn.in_flight_messages, conn.available_slots
+            );
+            println!(
+                "Processing speed: {} msgs/sec, total items: {}",
+                conn.average_items_per_second, conn.total_items_processed
+            );
+
+            if extra_statistics {
+                if let Some(stats) = conn.extra_statistics.as_ref() {
+                    println!("Latency (ms): {}", format_extra_statistics(stats));
+                }
+            }
+        }
+    }
 
     pub async fn run(
-        _: &clap::ArgMatches<'_>,
+        global: &clap::ArgMatches<'_>,
         params: &clap::ArgMatches<'_>,
         api: Api<'_>,
     ) -> CerberusResult<()> {
@@ -251,9 +577,31 @@ pub mod subscription {
         let group_id = params
             .value_of("group-id")
             .expect("Already checked by Clap");
-        let sub = api.subscription_raw(stream, group_id).await?;
 
-        serde_json::to_writer_pretty(std::io::stdout(), &sub).unwrap();
+        if params.is_present("raw") {
+            let sub = api.subscription_raw(stream, group_id).await?;
+
+            serde_json::to_writer_pretty(std::io::stdout(), &sub).unwrap();
+
+            return Ok(());
+        }
+
+        let info = api.subscription_info(stream, group_id).await?;
+        let connection = crate::common::create_connection_default(global).await?;
+        let parked_stream = crate::common::parked_stream_name(stream, group_id);
+        let parked_message_count =
+            crate::common::stream_event_count(&connection, parked_stream.as_str()).await?;
+
+        let detailed = Detailed {
+            info,
+            parked_message_count,
+        };
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&detailed);
+        }
+
+        print_detailed(&detailed);
 
         Ok(())
     }
@@ -261,6 +609,7 @@ pub mod subscription {
 
 pub mod projections {
     use crate::api::Api;
+    use crate::command::projection::stats;
     use crate::common::{CerberusError, CerberusResult};
 
     const KINDS: &[&str] = &[
@@ -271,6 +620,7 @@ pub mod projections {
         "all-non-transient",
     ];
 
+    #[allow(clippy::manual_contains)]
     fn is_valid_kind(submitted: &str) -> bool {
         KINDS.iter().any(|kind| submitted == *kind)
     }
@@ -290,12 +640,21 @@ pub mod projections {
         }
 
         let projections = api.projections(kind).await?;
+        let detailed = params.is_present("detailed");
 
         for proj in projections {
-            println!(
-                "{} [mode: {}] [status: {}]",
-                proj.name, proj.mode, proj.status
-            );
+            if !stats::matches_filters(params, &proj) {
+                continue;
+            }
+
+            if detailed {
+                stats::print_detailed(&proj);
+            } else {
+                println!(
+                    "{} [mode: {}] [status: {}]",
+                    proj.name, proj.mode, proj.status
+                );
+            }
         }
 
         Ok(())
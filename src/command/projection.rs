@@ -0,0 +1,1141 @@
+use crate::api::Api;
+use crate::common::{CerberusError, CerberusResult, CroppedProjectionInfo, OutputFormat};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+fn name_param<'a>(params: &'a clap::ArgMatches) -> &'a str {
+    params.value_of("name").expect("Already checked by Clap")
+}
+
+pub(crate) fn timeout_param(params: &clap::ArgMatches) -> CerberusResult<Duration> {
+    match params.value_of("timeout") {
+        Some(param) => crate::common::parse_duration("timeout", param),
+        None => Ok(DEFAULT_TIMEOUT),
+    }
+}
+
+/// The server can append details to a status, like `Completed/Stopped/Writing results`.
+/// Only the leading state matters when waiting for a transition.
+pub(crate) fn status_kind(status: &str) -> &str {
+    status.split('/').next().unwrap_or(status).trim()
+}
+
+fn is_faulted(info: &CroppedProjectionInfo) -> bool {
+    status_kind(&info.status) == "Faulted"
+}
+
+/// Statuses in which a projection doesn't process nor emit anything.
+pub(crate) fn is_stopped(info: &CroppedProjectionInfo) -> bool {
+    ["Stopped", "Completed", "Aborted", "Faulted"].contains(&status_kind(&info.status))
+}
+
+/// Polls the projection until it reaches one of the expected statuses or faults.
+pub(crate) async fn wait_for_status(
+    api: &Api<'_>,
+    name: &str,
+    expected: &[&str],
+    timeout: Duration,
+) -> CerberusResult<CroppedProjectionInfo> {
+    let started = Instant::now();
+
+    loop {
+        let info = api.projection_cropped_info(name).await?;
+        let kind = status_kind(&info.status);
+
+        if kind == "Faulted" || expected.contains(&kind) {
+            return Ok(info);
+        }
+
+        if started.elapsed() >= timeout {
+            return Err(CerberusError::user_fault(format!(
+                "Timed out after {}s waiting for projection [{}] to reach {} status. \
+                Last known status: {}",
+                timeout.as_secs(),
+                name,
+                expected.join(" or "),
+                info.status
+            )));
+        }
+
+        debug!(
+            "Projection [{}] status is {}, waiting...",
+            name, info.status
+        );
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+}
+
+pub(crate) async fn wait_for_deletion(
+    api: &Api<'_>,
+    name: &str,
+    timeout: Duration,
+) -> CerberusResult<()> {
+    let started = Instant::now();
+
+    while api.projection_cropped_info_opt(name).await?.is_some() {
+        if started.elapsed() >= timeout {
+            return Err(CerberusError::user_fault(format!(
+                "Timed out after {}s waiting for projection [{}] to be deleted",
+                timeout.as_secs(),
+                name
+            )));
+        }
+
+        tokio::time::delay_for(POLL_INTERVAL).await;
+    }
+
+    Ok(())
+}
+
+fn report(
+    global: &clap::ArgMatches,
+    name: &str,
+    info: &CroppedProjectionInfo,
+) -> CerberusResult<()> {
+    if OutputFormat::from_args(global) == OutputFormat::Json {
+        crate::common::print_json(info)?;
+    } else {
+        println!("Projection [{}] status: {}", name, info.status);
+    }
+
+    if is_faulted(info) {
+        let reason = info
+            .reason
+            .as_deref()
+            .unwrap_or("<unavailable faulted reason>");
+
+        return Err(CerberusError::user_fault(format!(
+            "Projection [{}] faulted:\n>> {}",
+            name, reason
+        )));
+    }
+
+    Ok(())
+}
+
+pub mod enable {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let timeout = super::timeout_param(params)?;
+
+        api.enable_projection(name).await?;
+
+        let info = super::wait_for_status(&api, name, &["Running", "Completed"], timeout).await?;
+
+        super::report(global, name, &info)
+    }
+}
+
+pub mod disable {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let timeout = super::timeout_param(params)?;
+
+        api.disable_projection(name).await?;
+
+        let info = super::wait_for_status(&api, name, &["Stopped", "Completed"], timeout).await?;
+
+        super::report(global, name, &info)
+    }
+}
+
+pub mod abort {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let timeout = super::timeout_param(params)?;
+
+        api.abort_projection(name).await?;
+
+        let info = super::wait_for_status(&api, name, &["Aborted", "Stopped"], timeout).await?;
+
+        super::report(global, name, &info)
+    }
+}
+
+pub mod reset {
+    use crate::api::Api;
+    use crate::common::{CerberusError, CerberusResult};
+    use std::time::Instant;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let timeout = super::timeout_param(params)?;
+        let before = api.projection_cropped_info(name).await?;
+        let started = Instant::now();
+
+        api.reset_projection(name).await?;
+
+        // The status a reset goes through may not last long enough to be seen
+        // while polling, the projection epoch and version stay bumped though.
+        if before.epoch.is_some() || before.version.is_some() {
+            loop {
+                let info = api.projection_cropped_info(name).await?;
+
+                if info.epoch > before.epoch || info.version > before.version {
+                    debug!(
+                        "Projection [{}] moved to epoch {:?}, version {:?}",
+                        name, info.epoch, info.version
+                    );
+                    break;
+                }
+
+                if started.elapsed() >= timeout {
+                    return Err(CerberusError::user_fault(format!(
+                        "Timed out after {}s waiting for projection [{}] to reset. \
+                        Last known status: {}",
+                        timeout.as_secs(),
+                        name,
+                        info.status
+                    )));
+                }
+
+                tokio::time::delay_for(super::POLL_INTERVAL).await;
+            }
+        }
+
+        let settled = ["Running", "Stopped", "Completed", "Aborted"];
+        let remaining = timeout.checked_sub(started.elapsed()).unwrap_or_default();
+        let info = super::wait_for_status(&api, name, &settled, remaining).await?;
+
+        super::report(global, name, &info)
+    }
+}
+
+pub mod delete {
+    use crate::api::{Api, DeleteProjectionOptions};
+    use crate::common::{CerberusResult, CroppedProjectionInfo, OutputFormat};
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Report<'a> {
+        name: &'a str,
+
+        /// Last known info before the projection got deleted.
+        info: &'a CroppedProjectionInfo,
+        deleted: bool,
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let timeout = super::timeout_param(params)?;
+        let format = OutputFormat::from_args(global);
+        let opts = DeleteProjectionOptions {
+            checkpoint_stream: params.is_present("delete-checkpoint-stream"),
+            state_stream: params.is_present("delete-state-stream"),
+            emitted_streams: params.is_present("delete-emitted-streams"),
+        };
+
+        let mut info = api.projection_cropped_info(name).await?;
+
+        // The server refuses to delete a running projection.
+        if !super::is_stopped(&info) {
+            if format == OutputFormat::Text {
+                println!("Disabling projection [{}] first...", name);
+            }
+
+            api.disable_projection(name).await?;
+
+            info = super::wait_for_status(&api, name, &["Stopped", "Completed"], timeout).await?;
+
+            if format == OutputFormat::Text {
+                println!("Projection [{}] status: {}", name, info.status);
+            }
+        }
+
+        api.delete_projection(name, opts).await?;
+        super::wait_for_deletion(&api, name, timeout).await?;
+
+        if format == OutputFormat::Json {
+            return crate::common::print_json(&Report {
+                name,
+                info: &info,
+                deleted: true,
+            });
+        }
+
+        println!("Projection [{}] deleted.", name);
+
+        Ok(())
+    }
+}
+
+/// Shared implementation of `projection state` and `projection result`.
+mod document {
+    use crate::api::Api;
+    use crate::common::{CerberusResult, OutputFormat};
+    use colored::Colorize;
+    use std::time::Duration;
+
+    const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_secs(2);
+
+    #[derive(Copy, Clone)]
+    pub enum Document {
+        State,
+        Result,
+    }
+
+    impl Document {
+        fn label(self) -> &'static str {
+            match self {
+                Document::State => "state",
+                Document::Result => "result",
+            }
+        }
+    }
+
+    async fn fetch(
+        api: &Api<'_>,
+        document: Document,
+        name: &str,
+        partition: Option<&str>,
+    ) -> CerberusResult<Option<serde_json::Value>> {
+        match document {
+            Document::State => api.projection_state(name, partition).await,
+            Document::Result => api.projection_result(name, partition).await,
+        }
+    }
+
+    pub fn pretty(value: &Option<serde_json::Value>) -> String {
+        match value {
+            Some(value) => serde_json::to_string_pretty(value).unwrap_or_default(),
+            None => String::new(),
+        }
+    }
+
+    pub fn print_diff(previous: &Option<serde_json::Value>, current: &Option<serde_json::Value>) {
+        let lhs = pretty(previous);
+        let rhs = pretty(current);
+
+        for d in diff::lines(lhs.as_str(), rhs.as_str()) {
+            match d {
+                diff::Result::Left(l) => println!("\t{} {}", "-".red(), l.red()),
+                diff::Result::Right(r) => println!("\t{} {}", "+".green(), r.green()),
+                diff::Result::Both(same, _) => println!("\t  {}", same),
+            }
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+        document: Document,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let partition = params.value_of("partition");
+        let watch = params.is_present("watch");
+        let format = OutputFormat::from_args(global);
+        let interval = match params.value_of("interval") {
+            Some(param) => crate::common::parse_duration("interval", param)?,
+            None => DEFAULT_WATCH_INTERVAL,
+        };
+
+        let mut previous = fetch(&api, document, name, partition).await?;
+
+        match format {
+            // In watch mode, every state is printed on its own line so the output can be piped.
+            OutputFormat::Json if watch => println!("{}", serde_json::json!(previous)),
+            OutputFormat::Json => crate::common::print_json(&previous)?,
+            OutputFormat::Text => match previous {
+                Some(_) => println!("{}", pretty(&previous)),
+                None => println!("Projection [{}] has no {} yet.", name, document.label()),
+            },
+        }
+
+        if !watch {
+            return Ok(());
+        }
+
+        loop {
+            tokio::time::delay_for(interval).await;
+
+            let current = fetch(&api, document, name, partition).await?;
+
+            if current == previous {
+                continue;
+            }
+
+            if format == OutputFormat::Json {
+                println!("{}", serde_json::json!(current));
+            } else {
+                println!("Projection [{}] {} changed:", name, document.label());
+                print_diff(&previous, &current);
+            }
+
+            previous = current;
+        }
+    }
+}
+
+pub mod state {
+    use super::document::{self, Document};
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        document::run(global, params, api, Document::State).await
+    }
+}
+
+pub mod result {
+    use super::document::{self, Document};
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        document::run(global, params, api, Document::Result).await
+    }
+}
+
+pub mod stats {
+    use crate::api::Api;
+    use crate::common::{CerberusResult, OutputFormat, Projection};
+
+    /// Applies --status and --mode filters, both case insensitive.
+    pub fn matches_filters(params: &clap::ArgMatches, proj: &Projection) -> bool {
+        let status_ok = params
+            .value_of("status")
+            .is_none_or(|status| super::status_kind(&proj.status).eq_ignore_ascii_case(status));
+
+        let mode_ok = params
+            .value_of("mode")
+            .is_none_or(|mode| proj.mode.eq_ignore_ascii_case(mode));
+
+        status_ok && mode_ok
+    }
+
+    pub fn print_detailed(proj: &Projection) {
+        println!("--------------------------------------------------------------");
+        println!(
+            "{} [mode: {}] [status: {}]",
+            proj.name, proj.mode, proj.status
+        );
+        println!("  Progress: {:.1}%", proj.progress);
+        println!("  Position: {}", proj.position);
+        println!("  Last checkpoint: {}", proj.last_checkpoint);
+
+        if !proj.checkpoint_status.is_empty() {
+            println!("  Checkpoint status: {}", proj.checkpoint_status);
+        }
+
+        println!(
+            "  Events processed after restart: {}",
+            proj.events_processed_after_restart
+        );
+        println!("  Buffered events: {}", proj.buffered_events);
+        println!("  Partitions cached: {}", proj.partitions_cached);
+        println!(
+            "  Reads/Writes in progress: {}/{}",
+            proj.reads_in_progress, proj.writes_in_progress
+        );
+        println!(
+            "  Write pending events before/after checkpoint: {}/{}",
+            proj.write_pending_events_before_checkpoint, proj.write_pending_events_after_checkpoint
+        );
+
+        if !proj.state_reason.is_empty() {
+            println!("  Last error: {}", proj.state_reason);
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let projections = match params.value_of("name") {
+            Some(name) => vec![api.projection_statistics(name).await?],
+            None => api.projections("any").await?,
+        };
+
+        let projections: Vec<Projection> = projections
+            .into_iter()
+            .filter(|proj| matches_filters(params, proj))
+            .collect();
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&projections);
+        }
+
+        for proj in projections.iter() {
+            print_detailed(proj);
+        }
+
+        Ok(())
+    }
+}
+
+pub mod test {
+    use crate::common::{CerberusError, CerberusResult, OutputFormat};
+    use boa_engine::{Context, Source};
+    use serde::Deserialize;
+    use std::collections::HashMap;
+    use std::io::BufRead;
+
+    const PRELUDE: &str = include_str!("projection_prelude.js");
+
+    /// How a payload is stored in the events file. Export archives base64
+    /// encode payloads that aren't JSON.
+    #[derive(Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+    #[serde(rename_all = "lowercase")]
+    enum Encoding {
+        #[default]
+        Json,
+        Base64,
+    }
+
+    /// One line of the events file. `number` is inferred from the position of
+    /// the event in its stream when missing.
+    #[derive(Deserialize)]
+    struct TestEvent {
+        stream: String,
+
+        #[serde(default)]
+        number: Option<i64>,
+
+        #[serde(default)]
+        id: Option<String>,
+
+        #[serde(rename = "type")]
+        event_type: String,
+
+        #[serde(default)]
+        data_encoding: Encoding,
+
+        #[serde(default)]
+        data: serde_json::Value,
+
+        #[serde(default)]
+        metadata_encoding: Encoding,
+
+        #[serde(default)]
+        metadata: serde_json::Value,
+    }
+
+    impl TestEvent {
+        /// Handlers get base64 encoded payloads back as raw text, like the
+        /// server would hand them over.
+        fn decode_payloads(&mut self, number: i64) -> CerberusResult<()> {
+            self.data = decode_payload(
+                &self.stream,
+                number,
+                "data",
+                self.data_encoding,
+                self.data.take(),
+            )?;
+            self.metadata = decode_payload(
+                &self.stream,
+                number,
+                "metadata",
+                self.metadata_encoding,
+                self.metadata.take(),
+            )?;
+            self.data_encoding = Encoding::Json;
+            self.metadata_encoding = Encoding::Json;
+
+            Ok(())
+        }
+    }
+
+    fn decode_payload(
+        stream: &str,
+        number: i64,
+        name: &str,
+        encoding: Encoding,
+        value: serde_json::Value,
+    ) -> CerberusResult<serde_json::Value> {
+        let encoded = match (encoding, value) {
+            (Encoding::Json, value) => return Ok(value),
+            (Encoding::Base64, serde_json::Value::String(encoded)) => encoded,
+            (Encoding::Base64, _) => {
+                return Err(CerberusError::user_fault(format!(
+                    "Event {}@{} {} is base64 encoded but isn't a string",
+                    number, stream, name
+                )))
+            }
+        };
+
+        let bytes = base64::decode(&encoded).map_err(|e| {
+            CerberusError::user_fault(format!(
+                "Event {}@{} {} isn't valid base64: {}",
+                number, stream, name, e
+            ))
+        })?;
+
+        Ok(serde_json::Value::String(
+            String::from_utf8_lossy(&bytes).into_owned(),
+        ))
+    }
+
+    #[derive(Deserialize, serde::Serialize)]
+    struct Report {
+        states: serde_json::Map<String, serde_json::Value>,
+
+        /// States reshaped by `transformBy` / `filterBy`, when the script uses them.
+        #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+        results: serde_json::Map<String, serde_json::Value>,
+        emitted: Vec<serde_json::Value>,
+        logs: Vec<String>,
+    }
+
+    fn raw_string(value: &serde_json::Value) -> String {
+        match value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => String::new(),
+            other => other.to_string(),
+        }
+    }
+
+    /// Builds the event object handlers receive, mimicking the server runtime.
+    fn to_js_event(event: &TestEvent, number: i64) -> serde_json::Value {
+        let is_json = event.data.is_object() || event.data.is_array();
+        let body = if is_json {
+            event.data.clone()
+        } else {
+            serde_json::Value::Null
+        };
+
+        let metadata = if event.metadata.is_object() {
+            event.metadata.clone()
+        } else {
+            serde_json::Value::Null
+        };
+
+        serde_json::json!({
+            "streamId": event.stream,
+            "sequenceNumber": number,
+            "eventType": event.event_type,
+            "eventId": event.id,
+            "isJson": is_json,
+            "body": body,
+            "data": body,
+            "bodyRaw": raw_string(&event.data),
+            "metadata": metadata,
+            "metadataRaw": raw_string(&event.metadata),
+        })
+    }
+
+    fn eval(context: &mut Context, code: &str) -> Result<boa_engine::JsValue, String> {
+        context
+            .eval(Source::from_bytes(code))
+            .map_err(|e| e.to_string())
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let script_filepath = params.value_of("script").expect("Already checked by Clap");
+        let events_filepath = params.value_of("events").expect("Already checked by Clap");
+
+        let script = std::fs::read_to_string(script_filepath).map_err(|e| {
+            CerberusError::user_fault(format!(
+                "There was an issue with the script's filepath you submitted: {}",
+                e
+            ))
+        })?;
+
+        let events_file = std::fs::File::open(events_filepath).map_err(|e| {
+            CerberusError::user_fault(format!(
+                "There was an issue with the events' filepath you submitted: {}",
+                e
+            ))
+        })?;
+
+        let mut context = Context::default();
+
+        eval(&mut context, PRELUDE).map_err(|e| {
+            CerberusError::dev_fault(format!("Failed to load projection API shim: {}", e))
+        })?;
+
+        eval(&mut context, script.as_str()).map_err(|e| {
+            CerberusError::user_fault(format!("Projection script failed to load:\n>> {}", e))
+        })?;
+
+        let mut next_numbers: HashMap<String, i64> = HashMap::new();
+        let mut count = 0usize;
+
+        for (idx, line) in std::io::BufReader::new(events_file).lines().enumerate() {
+            let line = line.map_err(|e| {
+                CerberusError::user_fault(format!("Failed to read events file: {}", e))
+            })?;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let mut event: TestEvent = serde_json::from_str(line.as_str()).map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Invalid event on line {} of the events file: {}",
+                    idx + 1,
+                    e
+                ))
+            })?;
+
+            let next = next_numbers.entry(event.stream.clone()).or_insert(0);
+            let number = event.number.unwrap_or(*next);
+
+            *next = number + 1;
+
+            event.decode_payloads(number)?;
+
+            let code = format!("__cerberus.feed({});", to_js_event(&event, number));
+
+            eval(&mut context, code.as_str()).map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Projection faulted on event {}@{} [{}] (line {}):\n>> {}",
+                    number,
+                    event.stream,
+                    event.event_type,
+                    idx + 1,
+                    e
+                ))
+            })?;
+
+            count += 1;
+        }
+
+        let output = eval(&mut context, "JSON.stringify(__cerberus.report())")
+            .and_then(|value| {
+                value
+                    .to_string(&mut context)
+                    .map(|s| s.to_std_string_escaped())
+                    .map_err(|e| e.to_string())
+            })
+            .map_err(|e| {
+                CerberusError::user_fault(format!("Failed to compute projection state:\n>> {}", e))
+            })?;
+
+        let report: Report = serde_json::from_str(output.as_str()).map_err(|e| {
+            CerberusError::dev_fault(format!("Failed to deserialize projection report: {}", e))
+        })?;
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&report);
+        }
+
+        println!("Processed {} events.", count);
+
+        for (partition, state) in report.states.iter() {
+            if partition.is_empty() {
+                println!("State:");
+            } else {
+                println!("State of partition [{}]:", partition);
+            }
+
+            println!(
+                "{}",
+                serde_json::to_string_pretty(state).unwrap_or_default()
+            );
+
+            if let Some(result) = report.results.get(partition) {
+                println!("Result:");
+                println!(
+                    "{}",
+                    serde_json::to_string_pretty(result).unwrap_or_default()
+                );
+            }
+        }
+
+        println!("Emitted events: {}", report.emitted.len());
+
+        for emitted in report.emitted.iter() {
+            println!(
+                "\t[{}] {} -> {}: {}",
+                emitted["kind"].as_str().unwrap_or_default(),
+                emitted["eventType"].as_str().unwrap_or_default(),
+                emitted["stream"].as_str().unwrap_or_default(),
+                emitted["body"]
+            );
+        }
+
+        for message in report.logs.iter() {
+            println!("log: {}", message);
+        }
+
+        Ok(())
+    }
+}
+
+pub mod dev {
+    use crate::api::{Api, ProjectionConf, UpdateProjectionConf};
+    use crate::common::{CerberusError, CerberusResult};
+    use colored::Colorize;
+    use std::time::{Duration, SystemTime};
+
+    const DEFAULT_WATCH_INTERVAL: Duration = Duration::from_millis(500);
+
+    fn last_modified(script_filepath: &str) -> CerberusResult<SystemTime> {
+        std::fs::metadata(script_filepath)
+            .and_then(|meta| meta.modified())
+            .map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "There was an issue with the script's filepath you submitted: {}",
+                    e
+                ))
+            })
+    }
+
+    /// Pushes the script to the server, creating a continuous projection if
+    /// needed, then resets and restarts it so it processes everything again.
+    async fn deploy(
+        api: &Api<'_>,
+        name: &str,
+        script: String,
+        params: &clap::ArgMatches<'_>,
+    ) -> CerberusResult<()> {
+        let track_emitted_streams = params.is_present("track-emitted-streams");
+
+        if api.projection_cropped_info_opt(name).await?.is_none() {
+            let conf = ProjectionConf {
+                name: Some(name),
+                kind: "continuous",
+                enabled: true,
+                emit: params.is_present("emit"),
+                checkpoints: true,
+                track_emitted_streams,
+                script,
+            };
+
+            api.create_projection(conf).await?;
+            println!("Projection [{}] created.", name);
+
+            return Ok(());
+        }
+
+        // Flags only turn settings on, what the projection already has is kept.
+        let config = api.projection_config(name).await?;
+        let conf = UpdateProjectionConf {
+            name,
+            emit: config.emit_enabled || params.is_present("emit"),
+            track_emitted_streams: config.track_emitted_streams || track_emitted_streams,
+            query: script.as_str(),
+        };
+
+        api.update_projection_query(conf).await?;
+        api.reset_projection(name).await?;
+        api.enable_projection(name).await?;
+
+        println!("Projection [{}] updated, reset and restarted.", name);
+
+        Ok(())
+    }
+
+    struct Watch<'a> {
+        name: &'a str,
+        script_filepath: &'a str,
+        deployed_version: Option<SystemTime>,
+        last_status: Option<String>,
+        last_state: Option<serde_json::Value>,
+    }
+
+    impl Watch<'_> {
+        /// Deploys the script when it changed, then reports status and state
+        /// changes.
+        async fn tick(
+            &mut self,
+            api: &Api<'_>,
+            params: &clap::ArgMatches<'_>,
+        ) -> CerberusResult<()> {
+            let modified = last_modified(self.script_filepath)?;
+
+            if self.deployed_version != Some(modified) {
+                let script = std::fs::read_to_string(self.script_filepath).map_err(|e| {
+                    CerberusError::user_fault(format!(
+                        "There was an issue with the script's filepath you submitted: {}",
+                        e
+                    ))
+                })?;
+
+                self.deployed_version = Some(modified);
+                self.last_status = None;
+                self.last_state = None;
+
+                // A broken deployment is reported but we keep watching for the next save.
+                if let Err(e) = deploy(api, self.name, script, params).await {
+                    println!("{} {}", "Deployment failed:".red(), e);
+                }
+            }
+
+            if let Some(info) = api.projection_cropped_info_opt(self.name).await? {
+                let faulted = super::status_kind(&info.status) == "Faulted";
+
+                if self.last_status.as_ref() != Some(&info.status) {
+                    if faulted {
+                        let reason = info
+                            .reason
+                            .as_deref()
+                            .unwrap_or("<unavailable faulted reason>");
+
+                        println!("{} {}", "Status: Faulted".red(), reason.red());
+                    } else {
+                        println!("Status: {}", info.status);
+                    }
+
+                    self.last_status = Some(info.status);
+                }
+
+                if !faulted {
+                    let state = api.projection_state(self.name, None).await?;
+
+                    if state != self.last_state {
+                        println!("State:");
+                        super::document::print_diff(&self.last_state, &state);
+                        self.last_state = state;
+                    }
+                }
+            }
+
+            Ok(())
+        }
+    }
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let script_filepath = params.value_of("script").expect("Already checked by Clap");
+        let interval = match params.value_of("interval") {
+            Some(param) => crate::common::parse_duration("interval", param)?,
+            None => DEFAULT_WATCH_INTERVAL,
+        };
+
+        let mut watch = Watch {
+            name,
+            script_filepath,
+            deployed_version: None,
+            last_status: None,
+            last_state: None,
+        };
+
+        println!(
+            "Watching [{}] for projection [{}], press Ctrl-C to stop.",
+            script_filepath, name
+        );
+
+        let mut last_error = None;
+
+        loop {
+            // The server restarting or the file being saved halfway shouldn't end the
+            // session, the next tick tries again. The same error is only reported once.
+            match watch.tick(&api, params).await {
+                Ok(()) => last_error = None,
+                Err(e) => {
+                    let message = e.to_string();
+
+                    if last_error.as_ref() != Some(&message) {
+                        println!("{} {}, retrying...", "Error:".red(), message);
+                        last_error = Some(message);
+                    }
+                }
+            }
+
+            tokio::time::delay_for(interval).await;
+        }
+    }
+}
+
+pub mod emitted {
+    use crate::api::Api;
+    use crate::common::{CerberusError, CerberusResult, OutputFormat};
+    use futures::TryStreamExt;
+    use serde::Serialize;
+
+    #[derive(Serialize)]
+    struct EmittedStream {
+        stream: String,
+
+        /// `None` when the stream is deleted.
+        event_count: Option<i64>,
+    }
+
+    async fn tracked_streams(
+        connection: &eventstore::Connection,
+        tracking_stream: &str,
+    ) -> CerberusResult<Vec<String>> {
+        let mut events = connection
+            .read_stream(tracking_stream)
+            .start_from_beginning()
+            .max_count(500)
+            .iterate_over();
+
+        let mut streams: Vec<String> = Vec::new();
+
+        while let Some(event) = events.try_next().await? {
+            let record = event.get_original_event();
+            let name = String::from_utf8_lossy(&record.data);
+            let name = name.trim().trim_matches('"');
+
+            if !name.is_empty() && !streams.iter().any(|existing| existing == name) {
+                streams.push(name.to_owned());
+            }
+        }
+
+        Ok(streams)
+    }
+
+    /// `None` when the stream got deleted already.
+    async fn event_count(
+        connection: &eventstore::Connection,
+        stream: &str,
+    ) -> CerberusResult<Option<i64>> {
+        let status = connection.read_event(stream, -1).execute().await?;
+
+        if let eventstore::ReadEventStatus::Deleted = status {
+            return Ok(None);
+        }
+
+        crate::common::stream_event_count(connection, stream)
+            .await
+            .map(Some)
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let name = super::name_param(params);
+        let delete = params.is_present("delete");
+        let tracking_stream = format!("$projections-{}-emittedstreams", name);
+
+        if delete {
+            if let Some(info) = api.projection_cropped_info_opt(name).await? {
+                if !super::is_stopped(&info) {
+                    return Err(CerberusError::user_fault(format!(
+                        "Projection [{}] is {}, disable or delete it before \
+                        deleting its emitted streams",
+                        name, info.status
+                    )));
+                }
+            }
+        }
+
+        let connection = crate::common::create_connection_default(global).await?;
+        let streams = tracked_streams(&connection, tracking_stream.as_str()).await?;
+        let mut emitted = Vec::with_capacity(streams.len());
+
+        for stream in streams {
+            let event_count = event_count(&connection, stream.as_str()).await?;
+
+            emitted.push(EmittedStream {
+                stream,
+                event_count,
+            });
+        }
+
+        if OutputFormat::from_args(global) == OutputFormat::Json && !delete {
+            return crate::common::print_json(&emitted);
+        }
+
+        if emitted.is_empty() {
+            println!(
+                "No emitted stream tracked in [{}]. Was projection [{}] created with \
+                --track-emitted-streams?",
+                tracking_stream, name
+            );
+
+            return Ok(());
+        }
+
+        for emitted in emitted.iter() {
+            match emitted.event_count {
+                Some(count) => println!("{} [events: {}]", emitted.stream, count),
+                None => println!("{} [deleted]", emitted.stream),
+            }
+        }
+
+        if !delete {
+            return Ok(());
+        }
+
+        let remaining: Vec<&EmittedStream> = emitted
+            .iter()
+            .filter(|emitted| emitted.event_count.is_some())
+            .collect();
+
+        let question = format!(
+            "Delete those {} streams and [{}]?",
+            remaining.len(),
+            tracking_stream
+        );
+
+        if !params.is_present("confirm") && !crate::common::ask_confirmation(question.as_str())? {
+            println!("Aborted, nothing was deleted.");
+
+            return Ok(());
+        }
+
+        for emitted in remaining {
+            connection
+                .delete_stream(emitted.stream.as_str())
+                .execute()
+                .await
+                .map_err(|e| {
+                    CerberusError::user_fault(format!(
+                        "Failed to delete stream [{}]: {}",
+                        emitted.stream, e
+                    ))
+                })?;
+
+            println!("Stream [{}] deleted.", emitted.stream);
+        }
+
+        connection
+            .delete_stream(tracking_stream.as_str())
+            .execute()
+            .await
+            .map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to delete stream [{}]: {}",
+                    tracking_stream, e
+                ))
+            })?;
+
+        println!("Stream [{}] deleted.", tracking_stream);
+
+        Ok(())
+    }
+}
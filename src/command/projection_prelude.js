@@ -0,0 +1,175 @@
+// Minimal shim of the EventStore projection API, used by `projection test`.
+// It only covers what a projection script needs to run against a list of
+// events: source selection, handlers, partitioning and emitted events.
+var __cerberus = (function () {
+    var sources = null;
+    var handlers = null;
+    var partitioner = null;
+    var transforms = [];
+    var states = {};
+    var emitted = [];
+    var logs = [];
+
+    function selector(filter) {
+        sources = filter;
+
+        return {
+            when: when,
+            foreachStream: foreachStream,
+            partitionBy: partitionBy
+        };
+    }
+
+    function chain() {
+        return {
+            outputState: function () { return chain(); },
+            outputTo: function () { return chain(); },
+            transformBy: function (f) { transforms.push(f); return chain(); },
+            filterBy: function (f) {
+                transforms.push(function (s) { return f(s) ? s : null; });
+                return chain();
+            }
+        };
+    }
+
+    function when(h) {
+        handlers = h;
+        return chain();
+    }
+
+    function foreachStream() {
+        partitioner = function (e) { return e.streamId; };
+        return { when: when };
+    }
+
+    function partitionBy(f) {
+        partitioner = f;
+        return { when: when };
+    }
+
+    function initialState() {
+        return handlers.$init ? handlers.$init() : {};
+    }
+
+    function feed(raw) {
+        if (handlers === null) {
+            throw new Error("The script doesn't call when() on a source selector");
+        }
+
+        if (!sources(raw.streamId)) {
+            return;
+        }
+
+        var handler = handlers[raw.eventType] || handlers.$any;
+
+        if (!handler) {
+            return;
+        }
+
+        var partition = partitioner ? partitioner(raw) : "";
+
+        if (partition === undefined || partition === null) {
+            return;
+        }
+
+        partition = String(partition);
+        raw.partition = partition;
+
+        if (!Object.prototype.hasOwnProperty.call(states, partition)) {
+            states[partition] = initialState();
+
+            if (handlers.$created) {
+                handlers.$created(states[partition], raw);
+            }
+        }
+
+        var result = handler(states[partition], raw);
+
+        if (result !== undefined) {
+            states[partition] = result;
+        }
+    }
+
+    // Like on the server, transformBy and filterBy only shape the result. They
+    // work on a copy so the state itself is left alone.
+    function report() {
+        var results = {};
+
+        if (transforms.length > 0) {
+            Object.keys(states).forEach(function (partition) {
+                var state = states[partition];
+                var result = state === undefined ? null : JSON.parse(JSON.stringify(state));
+
+                transforms.forEach(function (f) {
+                    if (result !== null) {
+                        result = f(result);
+                    }
+                });
+
+                results[partition] = result === undefined ? null : result;
+            });
+        }
+
+        return { states: states, results: results, emitted: emitted, logs: logs };
+    }
+
+    return {
+        selector: selector,
+        feed: feed,
+        report: report,
+        emitted: emitted,
+        logs: logs
+    };
+})();
+
+function fromAll() {
+    return __cerberus.selector(function () { return true; });
+}
+
+function fromStream(stream) {
+    return __cerberus.selector(function (s) { return s === stream; });
+}
+
+function fromStreams() {
+    var streams = Array.prototype.concat.apply([], arguments);
+    return __cerberus.selector(function (s) { return streams.indexOf(s) !== -1; });
+}
+
+function fromCategory(category) {
+    return __cerberus.selector(function (s) { return s.indexOf(category + "-") === 0; });
+}
+
+function fromCategories() {
+    var categories = Array.prototype.concat.apply([], arguments);
+    return __cerberus.selector(function (s) {
+        return categories.some(function (c) { return s.indexOf(c + "-") === 0; });
+    });
+}
+
+function options() {}
+
+function emit(stream, eventType, body, metadata) {
+    __cerberus.emitted.push({
+        kind: "emit",
+        stream: stream,
+        eventType: eventType,
+        body: body,
+        metadata: metadata === undefined ? null : metadata
+    });
+}
+
+function linkTo(stream, event, metadata) {
+    __cerberus.emitted.push({
+        kind: "link",
+        stream: stream,
+        eventType: "$>",
+        body: event.sequenceNumber + "@" + event.streamId,
+        metadata: metadata === undefined ? null : metadata
+    });
+}
+
+function log(message) {
+    __cerberus.logs.push(typeof message === "string" ? message : JSON.stringify(message));
+}
+
+var console = { log: log };
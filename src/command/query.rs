@@ -0,0 +1,126 @@
+use crate::api::{Api, DeleteProjectionOptions, ProjectionConf};
+use crate::command::projection;
+use crate::common::{CerberusError, CerberusResult, CroppedProjectionInfo, OutputFormat};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn load_script(params: &clap::ArgMatches) -> CerberusResult<String> {
+    if let Some(script) = params.value_of("eval") {
+        return Ok(script.to_owned());
+    }
+
+    let script_filepath = params
+        .value_of("script")
+        .expect("Either --script or --eval is checked by Clap");
+
+    std::fs::read_to_string(script_filepath).map_err(|e| {
+        CerberusError::user_fault(format!(
+            "There was an issue with the script's filepath you submitted: {}",
+            e
+        ))
+    })
+}
+
+fn query_name() -> String {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+
+    format!("cerberus-query-{}", millis)
+}
+
+/// Runs the query until it's done and fetches what it computed. The result
+/// of a query is its state when the script doesn't define a result.
+async fn execute(
+    api: &Api<'_>,
+    name: &str,
+    params: &clap::ArgMatches<'_>,
+) -> CerberusResult<(CroppedProjectionInfo, Option<serde_json::Value>)> {
+    let timeout = projection::timeout_param(params)?;
+    let info = projection::wait_for_status(api, name, &["Completed", "Stopped"], timeout).await?;
+
+    if projection::status_kind(&info.status) == "Faulted" {
+        return Ok((info, None));
+    }
+
+    let partition = params.value_of("partition");
+    let result = match api.projection_result(name, partition).await? {
+        Some(result) => Some(result),
+        None => api.projection_state(name, partition).await?,
+    };
+
+    Ok((info, result))
+}
+
+async fn cleanup(api: &Api<'_>, name: &str, params: &clap::ArgMatches<'_>) -> CerberusResult<()> {
+    let timeout = projection::timeout_param(params)?;
+    let info = api.projection_cropped_info(name).await?;
+    let stopped = ["Completed", "Stopped", "Aborted", "Faulted"];
+
+    if !stopped.contains(&projection::status_kind(&info.status)) {
+        api.abort_projection(name).await?;
+        projection::wait_for_status(api, name, &["Aborted", "Stopped"], timeout).await?;
+    }
+
+    api.delete_projection(name, DeleteProjectionOptions::default())
+        .await?;
+
+    projection::wait_for_deletion(api, name, timeout).await
+}
+
+pub async fn run(
+    global: &clap::ArgMatches<'_>,
+    params: &clap::ArgMatches<'_>,
+    api: Api<'_>,
+) -> CerberusResult<()> {
+    let script = load_script(params)?;
+    let name = query_name();
+
+    let conf = ProjectionConf {
+        name: Some(name.as_str()),
+        kind: "transient",
+        enabled: true,
+        emit: false,
+        checkpoints: false,
+        track_emitted_streams: false,
+        script,
+    };
+
+    api.create_projection(conf).await?;
+
+    let outcome = execute(&api, name.as_str(), params).await;
+
+    // The transient projection is removed whatever happened, a failing
+    // cleanup only gets reported so the query outcome isn't hidden.
+    if let Err(e) = cleanup(&api, name.as_str(), params).await {
+        eprintln!("Failed to delete query projection [{}]: {}", name, e);
+    }
+
+    let (info, result) = outcome?;
+
+    if projection::status_kind(&info.status) == "Faulted" {
+        let reason = info
+            .reason
+            .unwrap_or_else(|| "<unavailable faulted reason>".to_owned());
+
+        return Err(CerberusError::user_fault(format!(
+            "Query faulted:\n>> {}",
+            reason
+        )));
+    }
+
+    if OutputFormat::from_args(global) == OutputFormat::Json {
+        return crate::common::print_json(&result);
+    }
+
+    match result {
+        Some(result) => println!(
+            "{}",
+            serde_json::to_string_pretty(&result).unwrap_or_default()
+        ),
+
+        None => println!("Query completed without any result."),
+    }
+
+    Ok(())
+}
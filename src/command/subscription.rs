@@ -0,0 +1,1189 @@
+pub mod settings {
+    use crate::api::{NamedConsumerStrategy, SubscriptionConfig};
+    use crate::common::{CerberusError, CerberusResult};
+    use std::time::Duration;
+
+    /// Persistent subscription settings passed on the command line, shared by
+    /// the create and update commands. A `None` field means its flag wasn't
+    /// used, so the setting keeps its current (or default) value.
+    #[derive(Default)]
+    pub struct Settings {
+        pub resolve_link: Option<bool>,
+        pub start_from: Option<i64>,
+        pub extra_stats: Option<bool>,
+        pub message_timeout: Option<u64>,
+        pub max_retry_count: Option<u16>,
+        pub live_buffer_size: Option<u16>,
+        pub read_batch_size: Option<u16>,
+        pub history_buffer_size: Option<u16>,
+        pub checkpoint_after: Option<u64>,
+        pub min_checkpoint_count: Option<u16>,
+        pub max_checkpoint_count: Option<u16>,
+        pub max_subs_count: Option<u16>,
+        pub consumer_strategy: Option<NamedConsumerStrategy>,
+    }
+
+    fn parse_flag(params: &clap::ArgMatches, name: &str) -> Option<bool> {
+        if params.is_present(name) {
+            Some(true)
+        } else if params.is_present(format!("no-{}", name).as_str()) {
+            Some(false)
+        } else {
+            None
+        }
+    }
+
+    fn parse_number<A>(params: &clap::ArgMatches, name: &str) -> CerberusResult<Option<A>>
+    where
+        A: std::str::FromStr,
+        A::Err: std::fmt::Display,
+    {
+        if let Some(param) = params.value_of(name) {
+            let value = param.parse().map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to parse --{} number parameter: {}",
+                    name, e
+                ))
+            })?;
+
+            return Ok(Some(value));
+        }
+
+        Ok(None)
+    }
+
+    fn parse_strategy(params: &clap::ArgMatches) -> CerberusResult<Option<NamedConsumerStrategy>> {
+        if let Some(param) = params.value_of("consumer-strategy") {
+            let strategy = match param {
+                "dispatch-to-single" => NamedConsumerStrategy::DispatchToSingle,
+                "round-robin" => NamedConsumerStrategy::RoundRobin,
+                "pinned" => NamedConsumerStrategy::Pinned,
+                wrong => {
+                    return Err(CerberusError::user_fault(format!(
+                        "Unknown --consumer-strategy value: [{}]",
+                        wrong
+                    )));
+                }
+            };
+
+            return Ok(Some(strategy));
+        }
+
+        Ok(None)
+    }
+
+    impl Settings {
+        pub fn parse(params: &clap::ArgMatches) -> CerberusResult<Settings> {
+            Ok(Settings {
+                resolve_link: parse_flag(params, "resolve-link"),
+                start_from: parse_number(params, "start-from")?,
+                extra_stats: parse_flag(params, "extra-stats"),
+                message_timeout: parse_number(params, "message-timeout")?,
+                max_retry_count: parse_number(params, "max-retry-count")?,
+                live_buffer_size: parse_number(params, "live-buffer-size")?,
+                read_batch_size: parse_number(params, "read-batch-size")?,
+                history_buffer_size: parse_number(params, "history-buffer-size")?,
+                checkpoint_after: parse_number(params, "checkpoint-after")?,
+                min_checkpoint_count: parse_number(params, "min-checkpoint-count")?,
+                max_checkpoint_count: parse_number(params, "max-checkpoint-count")?,
+                max_subs_count: parse_number(params, "max-subs-count")?,
+                consumer_strategy: parse_strategy(params)?,
+            })
+        }
+
+        /// Overlays the passed settings on the TCP client settings.
+        pub fn apply_to_settings(&self, setts: &mut eventstore::PersistentSubscriptionSettings) {
+            if let Some(value) = self.resolve_link {
+                setts.resolve_link_tos = value;
+            }
+
+            if let Some(value) = self.start_from {
+                setts.start_from = value;
+            }
+
+            if let Some(value) = self.extra_stats {
+                setts.extra_stats = value;
+            }
+
+            if let Some(value) = self.message_timeout {
+                setts.msg_timeout = Duration::from_millis(value);
+            }
+
+            if let Some(value) = self.max_retry_count {
+                setts.max_retry_count = value;
+            }
+
+            if let Some(value) = self.live_buffer_size {
+                setts.live_buf_size = value;
+            }
+
+            if let Some(value) = self.read_batch_size {
+                setts.read_batch_size = value;
+            }
+
+            if let Some(value) = self.history_buffer_size {
+                setts.history_buf_size = value;
+            }
+
+            if let Some(value) = self.checkpoint_after {
+                setts.checkpoint_after = Duration::from_millis(value);
+            }
+
+            if let Some(value) = self.min_checkpoint_count {
+                setts.min_checkpoint_count = value;
+            }
+
+            if let Some(value) = self.max_checkpoint_count {
+                setts.max_checkpoint_count = value;
+            }
+
+            if let Some(value) = self.max_subs_count {
+                setts.max_subs_count = value;
+            }
+
+            if let Some(value) = self.consumer_strategy {
+                setts.named_consumer_strategy = match value {
+                    NamedConsumerStrategy::DispatchToSingle => {
+                        eventstore::SystemConsumerStrategy::DispatchToSingle
+                    }
+                    NamedConsumerStrategy::RoundRobin => {
+                        eventstore::SystemConsumerStrategy::RoundRobin
+                    }
+                    NamedConsumerStrategy::Pinned => eventstore::SystemConsumerStrategy::Pinned,
+                };
+            }
+        }
+
+        /// Overlays the passed settings on a configuration fetched through the HTTP API.
+        pub fn apply_to_config(&self, config: &mut SubscriptionConfig) {
+            if let Some(value) = self.resolve_link {
+                config.resolve_linktos = value;
+            }
+
+            if let Some(value) = self.start_from {
+                config.start_from = value;
+            }
+
+            if let Some(value) = self.extra_stats {
+                config.extra_statistics = value;
+            }
+
+            if let Some(value) = self.message_timeout {
+                config.message_timeout_milliseconds = value as usize;
+            }
+
+            if let Some(value) = self.max_retry_count {
+                config.max_retry_count = value as usize;
+            }
+
+            if let Some(value) = self.live_buffer_size {
+                config.live_buffer_size = value as usize;
+            }
+
+            if let Some(value) = self.read_batch_size {
+                config.read_batch_size = value as usize;
+            }
+
+            if let Some(value) = self.history_buffer_size {
+                config.buffer_size = value as usize;
+            }
+
+            if let Some(value) = self.checkpoint_after {
+                config.check_point_after_milliseconds = value as usize;
+            }
+
+            if let Some(value) = self.min_checkpoint_count {
+                config.min_check_point_count = value as usize;
+            }
+
+            if let Some(value) = self.max_checkpoint_count {
+                config.max_check_point_count = value as usize;
+            }
+
+            if let Some(value) = self.max_subs_count {
+                config.max_subscriber_count = value as usize;
+            }
+
+            if let Some(value) = self.consumer_strategy {
+                config.named_consumer_strategy = value;
+            }
+        }
+    }
+}
+
+pub mod checkpoint {
+    use crate::common::{CerberusError, CerberusResult};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    /// Event type the server uses when it writes a checkpoint.
+    pub const CHECKPOINT_EVENT_TYPE: &str = "SubscriptionCheckpoint";
+
+    /// Latest event of a persistent subscription checkpoint stream.
+    pub struct Checkpoint {
+        /// Last processed event number, when the payload is one. Servers
+        /// storing another kind of position leave it to `None`.
+        pub event_number: Option<i64>,
+        pub raw: String,
+        pub created_epoch: Option<i64>,
+    }
+
+    impl Checkpoint {
+        /// Time elapsed since the checkpoint was written.
+        pub fn age(&self) -> Option<Duration> {
+            let created = UNIX_EPOCH + Duration::from_millis(self.created_epoch? as u64);
+
+            SystemTime::now().duration_since(created).ok()
+        }
+
+        pub fn describe(&self) -> String {
+            let value = match self.event_number {
+                Some(number) => format!("last processed event number {}", number),
+                None => format!("undecoded position {}", self.raw),
+            };
+
+            match self.age() {
+                Some(age) => format!("{}, written {}s ago", value, age.as_secs()),
+                None => value,
+            }
+        }
+    }
+
+    pub async fn read(
+        connection: &eventstore::Connection,
+        stream: &str,
+        group_id: &str,
+    ) -> CerberusResult<Option<Checkpoint>> {
+        let checkpoint_stream = crate::common::checkpoint_stream_name(stream, group_id);
+        let result = connection
+            .read_event(checkpoint_stream.as_str(), -1)
+            .execute()
+            .await?;
+
+        match result {
+            eventstore::ReadEventStatus::Success(result) => {
+                let record = result.event.get_original_event();
+
+                Ok(Some(Checkpoint {
+                    event_number: serde_json::from_slice(&record.data).ok(),
+                    raw: String::from_utf8_lossy(&record.data).into_owned(),
+                    created_epoch: record.created_epoch,
+                }))
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    /// Writes a checkpoint the server will pick up the next time it loads the
+    /// persistent subscription.
+    pub async fn write(
+        connection: &eventstore::Connection,
+        stream: &str,
+        group_id: &str,
+        last_processed: i64,
+    ) -> CerberusResult<()> {
+        let checkpoint_stream = crate::common::checkpoint_stream_name(stream, group_id);
+        let event =
+            eventstore::EventData::json(CHECKPOINT_EVENT_TYPE, last_processed).map_err(|e| {
+                CerberusError::dev_fault(format!("Failed to serialize checkpoint: {}", e))
+            })?;
+
+        connection
+            .write_events(checkpoint_stream.as_str())
+            .push_event(event)
+            .execute()
+            .await
+            .map_err(|e| {
+                CerberusError::user_fault(format!(
+                    "Failed to write to [{}] stream: {}",
+                    checkpoint_stream, e
+                ))
+            })?;
+
+        Ok(())
+    }
+}
+
+pub mod reset {
+    use crate::api::Api;
+    use crate::common::{CerberusError, CerberusResult};
+    use std::time::{Duration, Instant};
+
+    const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    enum Strategy {
+        Checkpoint,
+        Recreate,
+    }
+
+    impl Strategy {
+        fn name(&self) -> &'static str {
+            match self {
+                Strategy::Checkpoint => "checkpoint",
+                Strategy::Recreate => "recreate",
+            }
+        }
+    }
+
+    /// Resolves `--to` into the number of the next event the group should receive.
+    async fn resolve_target(
+        connection: &eventstore::Connection,
+        stream: &str,
+        to: &str,
+    ) -> CerberusResult<i64> {
+        match to {
+            "start" => Ok(0),
+            "end" => Ok(crate::common::stream_last_event_number(connection, stream)
+                .await?
+                .map_or(0, |number| number + 1)),
+
+            number => {
+                let number: i64 = number.parse().map_err(|e| {
+                    CerberusError::user_fault(format!(
+                        "Failed to parse --to parameter, expected an event number, \
+                        start or end: {}",
+                        e
+                    ))
+                })?;
+
+                if number < 0 {
+                    return Err(CerberusError::user_fault(
+                        "--to event number must be positive",
+                    ));
+                }
+
+                Ok(number)
+            }
+        }
+    }
+
+    /// Polls the group until the server reports it resumed at the target. A
+    /// group nobody consumes keeps reporting -1, which proves nothing: in that
+    /// case the position is reported as unverified once the timeout expires.
+    async fn verify(
+        api: &Api<'_>,
+        stream: &str,
+        group_id: &str,
+        target: i64,
+        previous_opt: Option<i64>,
+        timeout: Duration,
+    ) -> CerberusResult<()> {
+        let started = Instant::now();
+
+        loop {
+            let info = api.subscription_info(stream, group_id).await?;
+            let last_processed = info.last_processed_event_number;
+
+            // A freshly loaded group reports -1 until it processes an event,
+            // which only tells something when resuming from the very start. When
+            // moving backward, still seeing the previous position means the
+            // server didn't reload the group yet.
+            let processed_since = last_processed != -1 || target == 0;
+            let restarted = processed_since
+                && last_processed >= target - 1
+                && previous_opt
+                    .is_none_or(|previous| previous < target || last_processed < previous);
+
+            if restarted {
+                println!(
+                    "Persistent subscription restarted, status: {}, last processed event number: {}.",
+                    info.status, last_processed
+                );
+
+                return Ok(());
+            }
+
+            if started.elapsed() >= timeout && !processed_since {
+                println!(
+                    "Persistent subscription status: {}. It didn't process any event within {}s, \
+                    so resuming from event {} couldn't be verified.",
+                    info.status,
+                    timeout.as_secs(),
+                    target
+                );
+
+                return Ok(());
+            }
+
+            if started.elapsed() >= timeout {
+                return Err(CerberusError::user_fault(format!(
+                    "Timed out after {}s waiting for the persistent subscription to restart at \
+                    event {}. Last processed event number: {}",
+                    timeout.as_secs(),
+                    target,
+                    last_processed
+                )));
+            }
+
+            tokio::time::delay_for(Duration::from_millis(500)).await;
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let stream = params.value_of("stream").expect("Already checked by Clap");
+        let group_id = params
+            .value_of("group-id")
+            .expect("Already checked by Clap");
+        let to = params.value_of("to").expect("Already checked by Clap");
+        let timeout = match params.value_of("timeout") {
+            Some(param) => crate::common::parse_duration("timeout", param)?,
+            None => DEFAULT_TIMEOUT,
+        };
+
+        let mut config = match api.subscription_opt(stream, group_id).await? {
+            Some(detail) => detail.config,
+            None => {
+                return Err(CerberusError::user_fault(format!(
+                    "Persistent subscription targetting [{}] stream on group [{}] doesn't exist.",
+                    stream, group_id
+                )));
+            }
+        };
+
+        let connection = crate::common::create_connection_default(global).await?;
+        let checkpoint_opt = super::checkpoint::read(&connection, stream, group_id).await?;
+
+        match checkpoint_opt.as_ref() {
+            Some(checkpoint) => println!("Current checkpoint: {}", checkpoint.describe()),
+            None => println!(
+                "No checkpoint written yet, the group starts from event {}.",
+                config.start_from
+            ),
+        }
+
+        let previous_opt = checkpoint_opt
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.event_number);
+
+        // Writing a checkpoint only works on servers storing event numbers in
+        // it, which is what an existing checkpoint tells us. Otherwise, we
+        // fall back on recreating the group with a new starting point.
+        let strategy = match params.value_of("strategy").unwrap_or("auto") {
+            "checkpoint" => Strategy::Checkpoint,
+            "recreate" => Strategy::Recreate,
+            _ if previous_opt.is_some() => Strategy::Checkpoint,
+            _ => Strategy::Recreate,
+        };
+
+        let target = resolve_target(&connection, stream, to).await?;
+
+        println!(
+            "The group will resume from event {} using the {} strategy. Connected clients \
+            will be disconnected.",
+            target,
+            strategy.name()
+        );
+
+        if !params.is_present("confirm")
+            && !crate::common::ask_confirmation("Do you want to proceed?")?
+        {
+            println!("Reset cancelled.");
+
+            return Ok(());
+        }
+
+        match strategy {
+            Strategy::Checkpoint => {
+                super::checkpoint::write(&connection, stream, group_id, target - 1).await?;
+
+                // Updating the group, even with the same configuration, makes
+                // the server reload it along with its latest checkpoint.
+                api.update_subscription(stream, group_id, config).await?;
+            }
+
+            Strategy::Recreate => {
+                let original = config.clone();
+
+                // Printed first so the group can still be recreated by hand if
+                // everything below goes wrong.
+                println!("Current group settings:");
+                crate::common::print_json(&original)?;
+
+                config.start_from = target;
+
+                api.delete_subscription(stream, group_id).await?;
+
+                if let Err(e) = api.create_subscription(stream, group_id, config).await {
+                    println!(
+                        "Failed to recreate the group, restoring its previous settings: {}",
+                        e
+                    );
+
+                    api.create_subscription(stream, group_id, original)
+                        .await
+                        .map_err(|restore| {
+                            CerberusError::user_fault(format!(
+                                "Failed to restore persistent subscription on [{}] with group \
+                                [{}]: {}. Its previous settings are printed above.",
+                                stream, group_id, restore
+                            ))
+                        })?;
+
+                    return Err(e);
+                }
+            }
+        }
+
+        verify(&api, stream, group_id, target, previous_opt, timeout).await
+    }
+}
+
+pub mod position {
+    use crate::api::Api;
+    use crate::common::{CerberusError, CerberusResult, OutputFormat};
+    use colored::Colorize;
+    use serde::Serialize;
+    use std::time::Duration;
+
+    const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(300);
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "camelCase")]
+    struct Position {
+        stream: String,
+        group: String,
+        status: String,
+        connections: usize,
+        stream_last_event_number: Option<i64>,
+        checkpoint: Option<i64>,
+        checkpoint_raw: Option<String>,
+        checkpoint_age_secs: Option<u64>,
+        lag: Option<i64>,
+        stale: bool,
+    }
+
+    /// Number of events between the checkpoint and the head of the stream.
+    /// Without checkpoint, the group hasn't processed anything since its
+    /// starting point.
+    fn lag(
+        last_event_opt: Option<i64>,
+        checkpoint_opt: Option<&super::checkpoint::Checkpoint>,
+        start_from: i64,
+    ) -> Option<i64> {
+        let last_event = last_event_opt.unwrap_or(-1);
+
+        match checkpoint_opt {
+            Some(checkpoint) => checkpoint
+                .event_number
+                .map(|number| (last_event - number).max(0)),
+
+            // A negative starting point means the group only reads new events.
+            None if start_from < 0 => Some(0),
+            None => Some((last_event - start_from + 1).max(0)),
+        }
+    }
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let stream = params.value_of("stream").expect("Already checked by Clap");
+        let group_id = params
+            .value_of("group-id")
+            .expect("Already checked by Clap");
+        let stale_after = match params.value_of("stale-after") {
+            Some(param) => crate::common::parse_duration("stale-after", param)?,
+            None => DEFAULT_STALE_AFTER,
+        };
+
+        let info = api.subscription_info(stream, group_id).await?;
+        let start_from = match info.config.as_ref() {
+            Some(config) => config.start_from,
+            None => match api.subscription_opt(stream, group_id).await? {
+                Some(detail) => detail.config.start_from,
+                None => {
+                    return Err(CerberusError::user_fault(format!(
+                        "Persistent subscription targetting [{}] stream on group [{}] doesn't exist.",
+                        stream, group_id
+                    )));
+                }
+            },
+        };
+
+        let connection = crate::common::create_connection_default(global).await?;
+        let checkpoint_opt = super::checkpoint::read(&connection, stream, group_id).await?;
+        let last_event_opt = crate::common::stream_last_event_number(&connection, stream).await?;
+        let lag = lag(last_event_opt, checkpoint_opt.as_ref(), start_from);
+        let age_opt = checkpoint_opt
+            .as_ref()
+            .and_then(|checkpoint| checkpoint.age());
+
+        // The server only checkpoints when clients ack events. Connected
+        // clients with events left to process and an old checkpoint means
+        // they don't make any progress.
+        let stale = !info.connections.is_empty()
+            && lag.is_some_and(|lag| lag > 0)
+            && age_opt.is_some_and(|age| age >= stale_after);
+
+        let position = Position {
+            stream: stream.to_owned(),
+            group: group_id.to_owned(),
+            status: info.status,
+            connections: info.connections.len(),
+            stream_last_event_number: last_event_opt,
+            checkpoint: checkpoint_opt
+                .as_ref()
+                .and_then(|checkpoint| checkpoint.event_number),
+            checkpoint_raw: checkpoint_opt
+                .as_ref()
+                .map(|checkpoint| checkpoint.raw.clone()),
+            checkpoint_age_secs: age_opt.map(|age| age.as_secs()),
+            lag,
+            stale,
+        };
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&position);
+        }
+
+        println!("Stream: {}", position.stream);
+        println!("Group: {}", position.group);
+        println!("Status: {}", position.status);
+        println!("Connections: {}", position.connections);
+
+        match position.stream_last_event_number {
+            Some(number) => println!("Stream last event number: {}", number),
+            None => println!("Stream last event number: <empty stream>"),
+        }
+
+        match checkpoint_opt.as_ref() {
+            Some(checkpoint) => println!("Checkpoint: {}", checkpoint.describe()),
+            None => println!("Checkpoint: <none, starts from {}>", start_from),
+        }
+
+        match position.lag {
+            Some(lag) => println!("Lag: {} events", lag),
+            None => println!("Lag: <unknown, checkpoint isn't an event number>"),
+        }
+
+        if stale {
+            println!(
+                "{}",
+                format!(
+                    "WARNING: the checkpoint hasn't moved for more than {}s while {} client(s) \
+                    are connected and events are waiting.",
+                    stale_after.as_secs(),
+                    position.connections
+                )
+                .yellow()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+pub mod bulk {
+    use super::settings::Settings;
+    use crate::api::{Api, NamedConsumerStrategy, SubscriptionConfig};
+    use crate::command::compliance::SubscriptionStrategy;
+    use crate::common::{CerberusError, CerberusResult};
+    use colored::Colorize;
+    use serde::Deserialize;
+
+    #[derive(Clone, Copy)]
+    enum Action {
+        Create,
+        Update,
+        Delete,
+    }
+
+    struct Target {
+        stream: String,
+        group: String,
+
+        /// Settings coming from the targets file, if any.
+        settings: Settings,
+    }
+
+    /// A targets file entry, either a CSV row or a TOML `[[subscription]]`
+    /// table. Every setting is optional, an empty cell or a missing key leaves
+    /// the setting to its current (or default) value.
+    #[derive(Deserialize)]
+    struct TargetEntry {
+        stream: String,
+        group: String,
+
+        #[serde(default)]
+        resolve_link: Option<bool>,
+
+        #[serde(default)]
+        start_from: Option<i64>,
+
+        #[serde(default)]
+        extra_stats: Option<bool>,
+
+        #[serde(default)]
+        msg_timeout_in_ms: Option<u64>,
+
+        #[serde(default)]
+        max_retry_count: Option<u16>,
+
+        #[serde(default)]
+        live_buffer_size: Option<u16>,
+
+        #[serde(default)]
+        read_batch_size: Option<u16>,
+
+        #[serde(default)]
+        buffer_size: Option<u16>,
+
+        #[serde(default)]
+        checkpoint_after_in_ms: Option<u64>,
+
+        #[serde(default)]
+        min_checkpoint_count: Option<u16>,
+
+        #[serde(default)]
+        max_checkpoint_count: Option<u16>,
+
+        #[serde(default)]
+        max_subs_count: Option<u16>,
+
+        #[serde(default)]
+        strategy: Option<SubscriptionStrategy>,
+    }
+
+    fn to_named_strategy(strategy: &SubscriptionStrategy) -> NamedConsumerStrategy {
+        match strategy {
+            SubscriptionStrategy::RoundRobin => NamedConsumerStrategy::RoundRobin,
+            SubscriptionStrategy::DispatchToSingle => NamedConsumerStrategy::DispatchToSingle,
+            SubscriptionStrategy::Pinned => NamedConsumerStrategy::Pinned,
+        }
+    }
+
+    /// TOML targets files share the compliance file layout.
+    #[derive(Deserialize)]
+    struct TomlTargets {
+        #[serde(rename = "subscription", default)]
+        subscriptions: Vec<TargetEntry>,
+    }
+
+    impl TargetEntry {
+        fn into_target(self) -> Target {
+            Target {
+                settings: Settings {
+                    resolve_link: self.resolve_link,
+                    start_from: self.start_from,
+                    extra_stats: self.extra_stats,
+                    message_timeout: self.msg_timeout_in_ms,
+                    max_retry_count: self.max_retry_count,
+                    live_buffer_size: self.live_buffer_size,
+                    read_batch_size: self.read_batch_size,
+                    history_buffer_size: self.buffer_size,
+                    checkpoint_after: self.checkpoint_after_in_ms,
+                    min_checkpoint_count: self.min_checkpoint_count,
+                    max_checkpoint_count: self.max_checkpoint_count,
+                    max_subs_count: self.max_subs_count,
+                    consumer_strategy: self.strategy.as_ref().map(to_named_strategy),
+                },
+                stream: self.stream,
+                group: self.group,
+            }
+        }
+    }
+
+    fn read_targets_file(path: &str) -> CerberusResult<Vec<Target>> {
+        if path.ends_with(".csv") {
+            let mut reader = csv::ReaderBuilder::new()
+                .trim(csv::Trim::All)
+                .from_path(path)
+                .map_err(|e| {
+                    CerberusError::user_fault(format!("Failed to open [{}]: {}", path, e))
+                })?;
+
+            let mut targets = Vec::new();
+
+            for row in reader.deserialize() {
+                let row: TargetEntry = row.map_err(|e| {
+                    CerberusError::user_fault(format!("Failed to parse [{}]: {}", path, e))
+                })?;
+
+                targets.push(row.into_target());
+            }
+
+            return Ok(targets);
+        }
+
+        if path.ends_with(".toml") {
+            let content = std::fs::read(path)?;
+            let targets: TomlTargets = toml::from_slice(content.as_slice()).map_err(|e| {
+                CerberusError::user_fault(format!("Failed to parse [{}]: {}", path, e))
+            })?;
+
+            return Ok(targets
+                .subscriptions
+                .into_iter()
+                .map(TargetEntry::into_target)
+                .collect());
+        }
+
+        Err(CerberusError::user_fault(format!(
+            "Unsupported targets file [{}], expected a .csv or .toml file",
+            path
+        )))
+    }
+
+    async fn glob_targets(
+        api: &Api<'_>,
+        stream_glob: &str,
+        group_glob: &str,
+    ) -> CerberusResult<Vec<Target>> {
+        let targets = api
+            .subscriptions()
+            .await?
+            .into_iter()
+            .filter(|sub| {
+                crate::common::glob_match(stream_glob, &sub.event_stream_id)
+                    && crate::common::glob_match(group_glob, &sub.group_name)
+            })
+            .map(|sub| Target {
+                stream: sub.event_stream_id,
+                group: sub.group_name,
+                settings: Settings::default(),
+            })
+            .collect();
+
+        Ok(targets)
+    }
+
+    async fn apply(
+        api: &Api<'_>,
+        action: Action,
+        target: &Target,
+        overrides: &Settings,
+    ) -> CerberusResult<()> {
+        let stream = target.stream.as_str();
+        let group = target.group.as_str();
+
+        match action {
+            Action::Create => {
+                let mut config = SubscriptionConfig::default();
+
+                target.settings.apply_to_config(&mut config);
+                overrides.apply_to_config(&mut config);
+
+                api.create_subscription(stream, group, config).await
+            }
+
+            Action::Update => {
+                let mut config = match api.subscription_opt(stream, group).await? {
+                    Some(detail) => detail.config,
+                    None => {
+                        return Err(CerberusError::user_fault(
+                            "Persistent subscription doesn't exist",
+                        ));
+                    }
+                };
+
+                target.settings.apply_to_config(&mut config);
+                overrides.apply_to_config(&mut config);
+
+                api.update_subscription(stream, group, config).await
+            }
+
+            Action::Delete => api.delete_subscription(stream, group).await,
+        }
+    }
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let (action, label) = match params.value_of("action") {
+            Some("create") => (Action::Create, "created"),
+            Some("update") => (Action::Update, "updated"),
+            Some("delete") => (Action::Delete, "deleted"),
+            _ => unreachable!("Already checked by Clap"),
+        };
+
+        let overrides = Settings::parse(params)?;
+        let targets = if let Some(path) = params.value_of("file") {
+            read_targets_file(path)?
+        } else if let Action::Create = action {
+            return Err(CerberusError::user_fault(
+                "Creating persistent subscriptions in bulk requires a --file listing them",
+            ));
+        } else if params.is_present("stream") || params.is_present("group") {
+            let stream_glob = params.value_of("stream").unwrap_or("*");
+            let group_glob = params.value_of("group").unwrap_or("*");
+
+            glob_targets(&api, stream_glob, group_glob).await?
+        } else {
+            return Err(CerberusError::user_fault(
+                "You must at least use --file, --stream or --group parameters",
+            ));
+        };
+
+        if targets.is_empty() {
+            println!("No persistent subscription matches.");
+
+            return Ok(());
+        }
+
+        println!(
+            "The following {} persistent subscription(s) will be {}:",
+            targets.len(),
+            label
+        );
+
+        for target in targets.iter() {
+            println!("\t[{}] on group [{}]", target.stream, target.group);
+        }
+
+        if !params.is_present("confirm")
+            && !crate::common::ask_confirmation("Do you want to proceed?")?
+        {
+            println!("Bulk operation cancelled.");
+
+            return Ok(());
+        }
+
+        let mut failures = 0;
+
+        for target in targets.iter() {
+            match apply(&api, action, target, &overrides).await {
+                Ok(()) => println!(
+                    "\t{} [{}] on group [{}] {}",
+                    "✓".green(),
+                    target.stream,
+                    target.group,
+                    label
+                ),
+
+                Err(e) => {
+                    failures += 1;
+
+                    println!(
+                        "\t{} [{}] on group [{}]: {}",
+                        "⨯".red(),
+                        target.stream,
+                        target.group,
+                        e.to_string().trim_end()
+                    );
+                }
+            }
+        }
+
+        println!(
+            "{} succeeded, {} failed.",
+            targets.len() - failures,
+            failures
+        );
+
+        if failures > 0 {
+            return Err(CerberusError::user_fault(format!(
+                "{} persistent subscription(s) couldn't be {}",
+                failures, label
+            )));
+        }
+
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::TomlTargets;
+
+        #[test]
+        fn toml_targets_leave_missing_settings_unset() {
+            let content = r#"
+                [[subscription]]
+                stream = "orders"
+                group = "billing"
+                max_retry_count = 20
+            "#;
+
+            let targets: TomlTargets = toml::from_str(content).unwrap();
+            let target = targets
+                .subscriptions
+                .into_iter()
+                .next()
+                .unwrap()
+                .into_target();
+
+            assert_eq!(target.stream, "orders");
+            assert_eq!(target.settings.max_retry_count, Some(20));
+            assert_eq!(target.settings.resolve_link, None);
+            assert_eq!(target.settings.start_from, None);
+            assert_eq!(target.settings.extra_stats, None);
+            assert!(target.settings.consumer_strategy.is_none());
+        }
+    }
+}
+
+/// Persistent subscription definitions, written using the compliance file
+/// format so the output can be fed back to `compliance` as well.
+pub mod definitions {
+    use crate::command::compliance::Compliance;
+    use crate::common::{CerberusError, CerberusResult};
+
+    pub fn is_json(path: &str, format_opt: Option<&str>) -> bool {
+        match format_opt {
+            Some(format) => format == "json",
+            None => path.ends_with(".json"),
+        }
+    }
+
+    pub fn serialize(compliance: &Compliance, json: bool) -> CerberusResult<String> {
+        let result = if json {
+            serde_json::to_string_pretty(compliance).map_err(|e| e.to_string())
+        } else {
+            toml::to_string(compliance).map_err(|e| e.to_string())
+        };
+
+        result.map_err(|e| {
+            CerberusError::dev_fault(format!(
+                "Failed to serialize subscription definitions: {}",
+                e
+            ))
+        })
+    }
+
+    pub fn deserialize(path: &str, content: &[u8], json: bool) -> CerberusResult<Compliance> {
+        let result = if json {
+            serde_json::from_slice(content).map_err(|e| e.to_string())
+        } else {
+            toml::from_slice(content).map_err(|e| e.to_string())
+        };
+
+        result.map_err(|e| CerberusError::user_fault(format!("Failed to parse [{}]: {}", path, e)))
+    }
+}
+
+pub mod export {
+    use crate::api::Api;
+    use crate::command::compliance::{Compliance, PersistentSubscriptionSettings, Subscription};
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let mut subscriptions = Vec::new();
+
+        for sub in api.subscriptions().await? {
+            // The subscription could have been deleted since we listed them.
+            if let Some(detail) = api
+                .subscription_opt(&sub.event_stream_id, &sub.group_name)
+                .await?
+            {
+                subscriptions.push(Subscription {
+                    stream: detail.event_stream_id,
+                    group: detail.group_name,
+                    settings: PersistentSubscriptionSettings::from_sub_config(&detail.config),
+                });
+            }
+        }
+
+        let count = subscriptions.len();
+        let compliance = Compliance::from_subscriptions(subscriptions);
+
+        match params.value_of("file") {
+            Some(path) => {
+                let json = super::definitions::is_json(path, params.value_of("format"));
+                let content = super::definitions::serialize(&compliance, json)?;
+
+                std::fs::write(path, content)?;
+
+                println!(
+                    "{} persistent subscription definition(s) exported to [{}].",
+                    count, path
+                );
+            }
+
+            None => {
+                let json = params.value_of("format") == Some("json");
+
+                print!("{}", super::definitions::serialize(&compliance, json)?);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub mod import {
+    use crate::api::Api;
+    use crate::common::{CerberusError, CerberusResult};
+    use colored::Colorize;
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let path = params.value_of("file").expect("Already checked by Clap");
+        let json = super::definitions::is_json(path, params.value_of("format"));
+        let content = std::fs::read(path)?;
+        let compliance = super::definitions::deserialize(path, &content, json)?;
+        let total = compliance.subscriptions.len();
+        let mut skipped = 0;
+        let mut failures = 0;
+
+        for sub in compliance.subscriptions {
+            let stream = sub.stream.as_str();
+            let group = sub.group.as_str();
+
+            if api.subscription_opt(stream, group).await?.is_some() {
+                skipped += 1;
+
+                println!(
+                    "\t{} [{}] on group [{}] already exists, skipped",
+                    "‐".yellow(),
+                    stream,
+                    group
+                );
+
+                continue;
+            }
+
+            match api
+                .create_subscription(stream, group, sub.settings.to_sub_config())
+                .await
+            {
+                Ok(()) => println!(
+                    "\t{} [{}] on group [{}] created",
+                    "✓".green(),
+                    stream,
+                    group
+                ),
+
+                Err(e) => {
+                    failures += 1;
+
+                    println!(
+                        "\t{} [{}] on group [{}]: {}",
+                        "⨯".red(),
+                        stream,
+                        group,
+                        e.to_string().trim_end()
+                    );
+                }
+            }
+        }
+
+        println!(
+            "{} created, {} skipped, {} failed.",
+            total - skipped - failures,
+            skipped,
+            failures
+        );
+
+        if failures > 0 {
+            return Err(CerberusError::user_fault(format!(
+                "{} persistent subscription(s) couldn't be imported",
+                failures
+            )));
+        }
+
+        Ok(())
+    }
+}
@@ -1,10 +1,13 @@
 pub mod subscription {
-    use crate::common::{CerberusError, CerberusResult, User};
+    use crate::api::Api;
+    use crate::command::subscription::settings::Settings;
+    use crate::common::{CerberusError, CerberusResult};
+    use colored::Colorize;
 
     pub async fn run(
-        global: &clap::ArgMatches<'_>,
+        _: &clap::ArgMatches<'_>,
         params: &clap::ArgMatches<'_>,
-        user_opt: Option<User<'_>>,
+        api: Api<'_>,
     ) -> CerberusResult<()> {
         let sub_info_opt = params.value_of("stream").and_then(|stream| {
             params
@@ -15,213 +18,48 @@ pub mod subscription {
         let (stream_name, group_id) =
             sub_info_opt.expect("Both stream and group-id params are previously checked by Clap");
 
-        let connection = crate::common::create_connection_default(global).await?;
-        let mut setts = eventstore::PersistentSubscriptionSettings::default();
-
-        setts.resolve_link_tos = params.is_present("resolve-link");
-        setts.extra_stats = params.is_present("extra-stats");
-
-        if let Some(param) = params.value_of("start-from") {
-            let start_from = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --start-from number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.start_from = start_from;
-        }
-
-        if let Some(param) = params.value_of("message-timeout") {
-            let millis = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --message-timeout number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.msg_timeout = std::time::Duration::from_millis(millis);
-        }
-
-        if let Some(param) = params.value_of("max-retry-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-retry-count number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.max_retry_count = count;
-        }
-
-        if let Some(param) = params.value_of("live-buffer-size") {
-            let size = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --live-buffer-size number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.live_buf_size = size;
-        }
-
-        if let Some(param) = params.value_of("read-batch-size") {
-            let size = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --read-batch-size number parameter: {}",
-                    e
-                ))
-            })?;
-
-            setts.read_batch_size = size;
-        }
-
-        if let Some(param) = params.value_of("history-buffer-size") {
-            let size = param.parse().map_err(|e| {
+        let settings = Settings::parse(params)?;
+        let detail = api
+            .subscription_opt(stream_name, group_id)
+            .await?
+            .ok_or_else(|| {
                 CerberusError::user_fault(format!(
-                    "Failed to parse --history-buffer-size number parameter: {}",
-                    e
+                    "You can't update a persistent subscription on stream [{}] \
+                    with group-id [{}] because the subscription doesn't exist",
+                    stream_name, group_id
                 ))
             })?;
 
-            setts.history_buf_size = size;
-        }
+        let mut config = detail.config;
+        let before = serde_json::to_string_pretty(&config).unwrap();
 
-        if let Some(param) = params.value_of("checkpoint-after") {
-            let millis = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --checkpoint-after number parameter: {}",
-                    e
-                ))
-            })?;
+        // Only the flags that were passed override the current configuration.
+        settings.apply_to_config(&mut config);
 
-            setts.checkpoint_after = std::time::Duration::from_millis(millis);
-        }
+        let after = serde_json::to_string_pretty(&config).unwrap();
 
-        if let Some(param) = params.value_of("min-checkpoint-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --min-checkpoint-count number parameter: {}",
-                    e
-                ))
-            })?;
+        if before == after {
+            println!("Persistent subscription is already up-to-date.");
 
-            setts.min_checkpoint_count = count;
+            return Ok(());
         }
 
-        if let Some(param) = params.value_of("max-checkpoint-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-checkpoint-count number parameter: {}",
-                    e
-                ))
-            })?;
+        println!("Configuration differences:");
+        println!("-------------------------");
 
-            setts.max_checkpoint_count = count;
+        for d in diff::lines(before.as_str(), after.as_str()) {
+            match d {
+                diff::Result::Left(l) => println!("\t{} {}", "-".red(), l.red()),
+                diff::Result::Right(r) => println!("\t{} {}", "+".green(), r.green()),
+                diff::Result::Both(same, _) => println!("\t  {}", same),
+            }
         }
 
-        if let Some(param) = params.value_of("max-subs-count") {
-            let count = param.parse().map_err(|e| {
-                CerberusError::user_fault(format!(
-                    "Failed to parse --max-subs-count number parameter: {}",
-                    e
-                ))
-            })?;
+        api.update_subscription(stream_name, group_id, config)
+            .await?;
 
-            setts.max_subs_count = count;
-        }
+        println!("Persistent subscription updated.");
 
-        if let Some(param) = params.value_of("consumer-strategy") {
-            let strategy = match param {
-                "dispatch-to-single" => eventstore::SystemConsumerStrategy::DispatchToSingle,
-                "round-robin" => eventstore::SystemConsumerStrategy::RoundRobin,
-                "pinned" => eventstore::SystemConsumerStrategy::Pinned,
-                wrong => {
-                    return Err(CerberusError::user_fault(format!(
-                        "Unknown --consumer-strategy value: [{}]",
-                        wrong
-                    )));
-                }
-            };
-
-            setts.named_consumer_strategy = strategy;
-        }
-
-        let mut cmd = connection
-            .update_persistent_subscription(stream_name, group_id)
-            .settings(setts);
-
-        if let Some(creds) = user_opt.map(|usr| usr.to_credentials()) {
-            cmd = cmd.credentials(creds);
-        }
-
-        match cmd.execute().await {
-            Err(e) => match e {
-                // Very unlikely we got that error because it should be handle by
-                // `eventstore::PersistActionError::AccessDenied`.
-                eventstore::OperationError::AccessDenied(_) => {
-                    Err(CerberusError::user_fault(format!(
-                        "Your current credentials doesn't allow you to update \
-                            a persistent subscription on [{}] stream.",
-                        stream_name
-                    )))
-                }
-
-                eventstore::OperationError::StreamDeleted(_) => {
-                    Err(CerberusError::user_fault(format!(
-                        "You can't create a persistent subscription on
-                            [{}] stream because that strean got deleted",
-                        stream_name
-                    )))
-                }
-
-                error => Err(CerberusError::user_fault(format!(
-                    "Can't create a persistent subscription on [{}] \
-                            stream because: {}.",
-                    stream_name, error
-                ))),
-            },
-
-            Ok(result) => match result {
-                eventstore::PersistActionResult::Failure(error) => match error {
-                    eventstore::PersistActionError::AccessDenied => {
-                        Err(CerberusError::user_fault(format!(
-                            "Your current credentials doesn't allow you to update \
-                                a persistent subscription on [{}] stream.",
-                            stream_name
-                        )))
-                    }
-
-                    eventstore::PersistActionError::AlreadyExists => {
-                        Err(CerberusError::user_fault(format!(
-                            "A persistent subscription already exists for the stream \
-                                [{}] with the group [{}]",
-                            stream_name, group_id
-                        )))
-                    }
-
-                    eventstore::PersistActionError::DoesNotExist => {
-                        Err(CerberusError::user_fault(format!(
-                            "You can't update a persistent subscription on stream [{}] \
-                                with group-id [{}] because the subscription doesn't exist",
-                            stream_name, group_id
-                        )))
-                    }
-
-                    eventstore::PersistActionError::Fail => Err(CerberusError::user_fault(format!(
-                        "Failed to update a persistent subscription on stream \
-                                [{}] with group [{}] but we don't have \
-                                information on why",
-                        stream_name, group_id
-                    ))),
-                },
-
-                _ => {
-                    println!("Persistent subscription updated.");
-
-                    Ok(())
-                }
-            },
-        }
+        Ok(())
     }
 }
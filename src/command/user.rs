@@ -0,0 +1,214 @@
+fn login_param<'a>(params: &'a clap::ArgMatches) -> &'a str {
+    params.value_of("LOGIN").expect("Already checked by Clap")
+}
+
+pub mod list {
+    use crate::api::Api;
+    use crate::common::{CerberusResult, OutputFormat};
+
+    pub async fn run(
+        global: &clap::ArgMatches<'_>,
+        _: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let users = api.users().await?;
+
+        if OutputFormat::from_args(global) == OutputFormat::Json {
+            return crate::common::print_json(&users);
+        }
+
+        for user in users {
+            println!("--------------------------------------------------------------");
+            println!("Login: {}", user.login_name);
+            println!("Full name: {}", user.full_name);
+            println!("Groups: {}", user.groups.join(", "));
+            println!("Disabled: {}", user.disabled);
+
+            if let Some(date) = user.date_last_updated {
+                println!("Last updated: {}", date);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub mod create {
+    use crate::api::{Api, UserCreation};
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+        let full_name = params.value_of("full-name").unwrap_or(login);
+        let groups = params.values_of_lossy("group").unwrap_or_default();
+        let password = crate::common::prompt_new_password()?;
+
+        let user = UserCreation {
+            login_name: login,
+            full_name,
+            groups,
+            password: password.as_str(),
+        };
+
+        api.create_user(user).await?;
+
+        println!("User [{}] created.", login);
+
+        Ok(())
+    }
+}
+
+pub mod update {
+    use crate::api::{Api, UserUpdate};
+    use crate::common::{CerberusError, CerberusResult};
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+        let current = api.user(login).await?;
+        let full_name = params
+            .value_of("full-name")
+            .unwrap_or(current.full_name.as_str());
+
+        let mut groups = match params.value_of("groups") {
+            Some(value) => value
+                .split(',')
+                .map(|group| group.trim())
+                .filter(|group| !group.is_empty())
+                .map(|group| group.to_owned())
+                .collect(),
+
+            None => current.groups.clone(),
+        };
+
+        if let Some(added) = params.values_of("add-group") {
+            for group in added {
+                if !groups.iter().any(|existing| existing == group) {
+                    groups.push(group.to_owned());
+                }
+            }
+        }
+
+        if let Some(removed) = params.values_of("remove-group") {
+            for group in removed {
+                if !groups.iter().any(|existing| existing == group) {
+                    return Err(CerberusError::user_fault(format!(
+                        "User [{}] doesn't belong to group [{}]",
+                        login, group
+                    )));
+                }
+
+                groups.retain(|existing| existing != group);
+            }
+        }
+
+        if full_name == current.full_name && groups == current.groups {
+            println!("User [{}] is already up-to-date.", login);
+
+            return Ok(());
+        }
+
+        let update = UserUpdate { full_name, groups };
+
+        api.update_user(login, update).await?;
+
+        println!("User [{}] updated.", login);
+
+        Ok(())
+    }
+}
+
+pub mod enable {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+        enabled: bool,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+
+        if enabled {
+            api.enable_user(login).await?;
+            println!("User [{}] enabled.", login);
+        } else {
+            api.disable_user(login).await?;
+            println!("User [{}] disabled.", login);
+        }
+
+        Ok(())
+    }
+}
+
+pub mod delete {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn run(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+        let question = format!("Delete user [{}]?", login);
+
+        if !params.is_present("confirm") && !crate::common::ask_confirmation(question.as_str())? {
+            println!("Aborted, user [{}] was not deleted.", login);
+
+            return Ok(());
+        }
+
+        api.delete_user(login).await?;
+
+        println!("User [{}] deleted.", login);
+
+        Ok(())
+    }
+}
+
+pub mod password {
+    use crate::api::Api;
+    use crate::common::CerberusResult;
+
+    pub async fn reset(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+        let new_password = crate::common::prompt_new_password()?;
+
+        api.reset_user_password(login, new_password.as_str())
+            .await?;
+
+        println!("Password of user [{}] reset.", login);
+
+        Ok(())
+    }
+
+    pub async fn change(
+        _: &clap::ArgMatches<'_>,
+        params: &clap::ArgMatches<'_>,
+        api: Api<'_>,
+    ) -> CerberusResult<()> {
+        let login = super::login_param(params);
+        let current_password = crate::common::prompt_password("Current password: ")?;
+        let new_password = crate::common::prompt_new_password()?;
+
+        api.change_user_password(login, current_password.as_str(), new_password.as_str())
+            .await?;
+
+        println!("Password of user [{}] changed.", login);
+
+        Ok(())
+    }
+}
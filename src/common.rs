@@ -20,6 +20,7 @@ impl<'a> User<'a> {
         })
     }
 
+    #[allow(clippy::wrong_self_convention)]
     pub fn to_credentials(&'a self) -> eventstore::Credentials {
         eventstore::Credentials::new(
             self.login.to_owned(),
@@ -28,6 +29,140 @@ impl<'a> User<'a> {
     }
 }
 
+/// How commands supporting structured output should render their results.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+impl OutputFormat {
+    pub fn from_args(global: &clap::ArgMatches) -> OutputFormat {
+        match global.value_of("output") {
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Text,
+        }
+    }
+}
+
+pub fn print_json<A: Serialize>(value: &A) -> CerberusResult<()> {
+    serde_json::to_writer_pretty(std::io::stdout(), value).map_err(|e| {
+        CerberusError::dev_fault(format!("Failed to serialize output as JSON: {}", e))
+    })?;
+
+    println!();
+
+    Ok(())
+}
+
+/// Reads a password from the terminal without echoing it.
+pub fn prompt_password(prompt: &str) -> CerberusResult<String> {
+    let password = rpassword::read_password_from_tty(Some(prompt))
+        .map_err(|e| CerberusError::user_fault(format!("Failed to read password: {}", e)))?;
+
+    Ok(password)
+}
+
+/// Asks for a new password twice and makes sure both entries match.
+pub fn prompt_new_password() -> CerberusResult<String> {
+    let password = prompt_password("New password: ")?;
+
+    if password.is_empty() {
+        return Err(CerberusError::user_fault("Password can't be empty"));
+    }
+
+    let confirmation = prompt_password("Confirm new password: ")?;
+
+    if password != confirmation {
+        return Err(CerberusError::user_fault("Passwords don't match"));
+    }
+
+    Ok(password)
+}
+
+/// Asks a yes/no question on the terminal. Anything but `y` or `yes` is a no.
+pub fn ask_confirmation(question: &str) -> CerberusResult<bool> {
+    use std::io::Write;
+
+    print!("{} [y/N] ", question);
+    std::io::stdout()
+        .flush()
+        .map_err(|e| CerberusError::user_fault(format!("Failed to write prompt: {}", e)))?;
+
+    let mut answer = String::new();
+
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| CerberusError::user_fault(format!("Failed to read answer: {}", e)))?;
+
+    let answer = answer.trim().to_lowercase();
+
+    Ok(answer == "y" || answer == "yes")
+}
+
+/// Parses durations like `500ms`, `2s` or `1m`. A bare number is a number of seconds.
+pub fn parse_duration(param: &str, value: &str) -> CerberusResult<Duration> {
+    let value = value.trim();
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(idx) => value.split_at(idx),
+        None => (value, "s"),
+    };
+
+    let number: u64 = number.parse().map_err(|e| {
+        CerberusError::user_fault(format!(
+            "Failed to parse --{} duration [{}]: {}",
+            param, value, e
+        ))
+    })?;
+
+    // A zero interval would have watch loops hammer the server.
+    if number == 0 {
+        return Err(CerberusError::user_fault(format!(
+            "--{} duration must be greater than 0",
+            param
+        )));
+    }
+
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        "h" => Ok(Duration::from_secs(number * 3_600)),
+        _ => Err(CerberusError::user_fault(format!(
+            "Unsupported --{} duration unit [{}], expected ms, s, m or h",
+            param, unit
+        ))),
+    }
+}
+
+/// Matches a text against a glob pattern, where `*` matches any sequence of
+/// characters and `?` exactly one.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == text[t]) {
+            p += 1;
+            t += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            backtrack = Some((p, t));
+            p += 1;
+        } else if let Some((star, matched)) = backtrack {
+            // Let the last star swallow one more character and try again.
+            backtrack = Some((star, matched + 1));
+            p = star + 1;
+            t = matched + 1;
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct NodeInfo {
     #[serde(rename = "esVersion")]
@@ -69,10 +204,49 @@ pub struct Projections {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Projection {
     pub name: String,
     pub mode: String,
     pub status: String,
+
+    /// In case of a 'Faulted' status, gives an insight of what
+    /// wrong happened.
+    #[serde(default)]
+    pub state_reason: String,
+
+    #[serde(default)]
+    pub progress: f64,
+
+    #[serde(default)]
+    pub position: String,
+
+    #[serde(default)]
+    pub last_checkpoint: String,
+
+    #[serde(default)]
+    pub checkpoint_status: String,
+
+    #[serde(default)]
+    pub events_processed_after_restart: i64,
+
+    #[serde(default)]
+    pub buffered_events: i64,
+
+    #[serde(default)]
+    pub reads_in_progress: i64,
+
+    #[serde(default)]
+    pub writes_in_progress: i64,
+
+    #[serde(default)]
+    pub write_pending_events_before_checkpoint: i64,
+
+    #[serde(default)]
+    pub write_pending_events_after_checkpoint: i64,
+
+    #[serde(default)]
+    pub partitions_cached: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -90,6 +264,14 @@ pub struct CroppedProjectionInfo {
     /// wrong happened.
     #[serde(rename = "stateReason")]
     pub reason: Option<String>,
+
+    /// Both go up every time the projection is reset, which is how we tell a
+    /// reset went through.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epoch: Option<i64>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -197,6 +379,90 @@ pub fn list_tcp_endpoints(params: &clap::ArgMatches) -> CerberusResult<Vec<Socke
     }
 }
 
+/// Turns a recorded event back into an event that can be written to a
+/// stream. The original event id is kept so the write stays idempotent.
+///
+/// Event metadata is carried over as well. Both `copy-events` and `export`
+/// rely on that: before, `export` used to drop it when copying to another
+/// database.
+pub fn record_to_event_data(record: &eventstore::RecordedEvent) -> eventstore::EventData {
+    let data = if record.is_json {
+        let json: &serde_json::value::RawValue = record.as_json().unwrap();
+
+        eventstore::EventData::json(&*record.event_type, json).unwrap()
+    } else {
+        eventstore::EventData::binary(&*record.event_type, record.data.clone())
+    };
+
+    let data = match record_metadata(record) {
+        Some(metadata) => data.metadata_as_binary(metadata),
+        None => data,
+    };
+
+    data.id(record.event_id)
+}
+
+/// Metadata to write along with a copy of `record`, if it has any.
+fn record_metadata(record: &eventstore::RecordedEvent) -> Option<bytes::Bytes> {
+    if record.metadata.is_empty() {
+        None
+    } else {
+        Some(record.metadata.clone())
+    }
+}
+
+pub fn checkpoint_stream_name(stream: &str, group_id: &str) -> String {
+    format!(
+        "$persistentsubscription-{}::{}-checkpoint",
+        stream, group_id
+    )
+}
+
+pub fn parked_stream_name(stream: &str, group_id: &str) -> String {
+    format!("$persistentsubscription-{}::{}-parked", stream, group_id)
+}
+
+/// Number of the last event of a stream, `None` if the stream has no event.
+pub async fn stream_last_event_number(
+    connection: &eventstore::Connection,
+    stream: &str,
+) -> CerberusResult<Option<i64>> {
+    match connection.read_event(stream, -1).execute().await? {
+        eventstore::ReadEventStatus::Success(result) => {
+            Ok(Some(result.event.get_original_event().event_number))
+        }
+
+        _ => Ok(None),
+    }
+}
+
+/// Counts the events of a stream as its next event number minus its
+/// truncation point (`$tb`), capped by `$maxCount`. Events past `$maxAge`
+/// aren't known without reading them, so they are still counted.
+pub async fn stream_event_count(
+    connection: &eventstore::Connection,
+    stream: &str,
+) -> CerberusResult<i64> {
+    let next_number = match stream_last_event_number(connection, stream).await? {
+        Some(number) => number + 1,
+        None => return Ok(0),
+    };
+
+    let (truncate_before, max_count) =
+        match connection.read_stream_metadata(stream).execute().await? {
+            eventstore::StreamMetadataResult::Success(versioned) => (
+                versioned.metadata.truncate_before.unwrap_or(0) as i64,
+                versioned.metadata.max_count.map(|count| count as i64),
+            ),
+
+            _ => (0, None),
+        };
+
+    let count = (next_number - truncate_before).max(0);
+
+    Ok(max_count.map_or(count, |max_count| count.min(max_count)))
+}
+
 pub fn list_hosts<'a>(params: &'a clap::ArgMatches) -> Vec<&'a str> {
     if let Some(hosts) = params.values_of("host") {
         hosts.collect()
@@ -305,3 +571,78 @@ pub fn public_http_port(global: &clap::ArgMatches) -> u16 {
 
     ports.pop().unwrap().parse().unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_event(metadata: &[u8]) -> eventstore::RecordedEvent {
+        eventstore::RecordedEvent {
+            event_stream_id: "orders-1".to_owned(),
+            event_id: uuid::Uuid::new_v4(),
+            event_number: 0,
+            event_type: "OrderPlaced".to_owned(),
+            data: bytes::Bytes::from_static(b"{}"),
+            metadata: bytes::Bytes::copy_from_slice(metadata),
+            is_json: true,
+            created: None,
+            created_epoch: None,
+        }
+    }
+
+    #[test]
+    fn cropped_projection_info_reads_epoch_and_version() {
+        let info: CroppedProjectionInfo = serde_json::from_str(
+            r#"{"status":"Running","stateReason":"","epoch":2,"version":5,"progress":100.0}"#,
+        )
+        .unwrap();
+
+        assert_eq!(info.epoch, Some(2));
+        assert_eq!(info.version, Some(5));
+
+        let info: CroppedProjectionInfo = serde_json::from_str(r#"{"status":"Stopped"}"#).unwrap();
+
+        assert_eq!(info.epoch, None);
+        assert_eq!(
+            serde_json::to_string(&info).unwrap(),
+            r#"{"status":"Stopped","stateReason":null}"#
+        );
+    }
+
+    #[test]
+    fn parse_duration_supports_units() {
+        assert_eq!(
+            parse_duration("interval", "250ms").unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            parse_duration("interval", "3").unwrap(),
+            Duration::from_secs(3)
+        );
+        assert_eq!(
+            parse_duration("interval", "2m").unwrap(),
+            Duration::from_secs(120)
+        );
+    }
+
+    #[test]
+    fn parse_duration_rejects_zero() {
+        assert!(parse_duration("interval", "0").is_err());
+        assert!(parse_duration("interval", "0ms").is_err());
+    }
+
+    #[test]
+    fn record_metadata_is_copied() {
+        let record = recorded_event(br#"{"$correlationId":"42"}"#);
+
+        assert_eq!(
+            record_metadata(&record).as_deref(),
+            Some(&br#"{"$correlationId":"42"}"#[..])
+        );
+    }
+
+    #[test]
+    fn empty_record_metadata_is_skipped() {
+        assert_eq!(record_metadata(&recorded_event(b"")), None);
+    }
+}
@@ -8,9 +8,91 @@ mod api;
 mod command;
 mod common;
 
-use clap::{App, Arg, SubCommand};
+use clap::{App, AppSettings, Arg, SubCommand};
 use std::env;
 
+/// Persistent subscription settings, shared by create-subscription and
+/// update-subscription.
+fn subscription_settings_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("resolve-link")
+            .help("Determines whether any link events encountered in the stream will be resolved")
+            .long("resolve-link"),
+        Arg::with_name("no-resolve-link")
+            .help("Stop resolving link events, only useful when updating a subscription")
+            .long("no-resolve-link")
+            .conflicts_with("resolve-link"),
+        Arg::with_name("start-from")
+            .help("Where the subscription should start from (event number) [default: -1]")
+            .long("start-from")
+            .value_name("EVENT_NUMBER")
+            .takes_value(true),
+        Arg::with_name("extra-stats")
+            .help("Whether or not in depth latency statistics should be tracked on this subscription")
+            .long("extra-stats"),
+        Arg::with_name("no-extra-stats")
+            .help("Stop tracking in depth latency statistics, only useful when updating a subscription")
+            .long("no-extra-stats")
+            .conflicts_with("extra-stats"),
+        Arg::with_name("message-timeout")
+            .help(
+                "The amount of time, in milliseconds, after which a message \
+                should be considered to be timeout and retried [default: 30secs]")
+            .value_name("MILLISECONDS")
+            .long("message-timeout")
+            .takes_value(true),
+        Arg::with_name("max-retry-count")
+            .help(
+                "The maximum number of retries (due to timeout) before a message \
+                get considered to be parked [default: 10]")
+            .long("max-retry-count")
+            .value_name("COUNT")
+            .takes_value(true),
+        Arg::with_name("live-buffer-size")
+            .help("The size of the buffer listening to live messages as they happen [default: 500]")
+            .long("live-buffer-size")
+            .value_name("BUFFER_SIZE")
+            .takes_value(true),
+        Arg::with_name("read-batch-size")
+            .help("The number of events read at a time when paging in history [default: 500]")
+            .long("read-batch-size")
+            .value_name("BUFFER_SIZE")
+            .takes_value(true),
+        Arg::with_name("history-buffer-size")
+            .help("The number of events read at a time when paging through history [default: 500]")
+            .long("history-buffer-size")
+            .value_name("BUFFER_SIZE")
+            .takes_value(true),
+        Arg::with_name("checkpoint-after")
+            .help("The amount of time, in milliseconds, to try checkpoint after [default: 2secs]")
+            .long("checkpoint-after")
+            .value_name("MILLISECONDS")
+            .takes_value(true),
+        Arg::with_name("min-checkpoint-count")
+            .help("The minimum number of messages to checkpoint [default: 10]")
+            .long("min-checkpoint-count")
+            .value_name("COUNT")
+            .takes_value(true),
+        Arg::with_name("max-checkpoint-count")
+            .help(
+                "The maximum number of messages to checkpoint. \
+                If this number is reached , a checkpoint will be forced [default: 1000]")
+            .long("max-checkpoint-count")
+            .value_name("COUNT")
+            .takes_value(true),
+        Arg::with_name("max-subs-count")
+            .help("The maximum number of subscribers allowed [default: 0 (means no limit)]")
+            .long("max-subs-count")
+            .value_name("COUNT")
+            .takes_value(true),
+        Arg::with_name("consumer-strategy")
+            .help("The strategy to use for distributing events to client consumers [default: RoundRobin]")
+            .long("consumer-strategy")
+            .value_name("STRATEGY")
+            .takes_value(true),
+    ]
+}
+
 #[tokio::main]
 async fn main() {
     let matches = App::new("Cerberus")
@@ -64,6 +146,13 @@ async fn main() {
             .help("Max operation retry count in case of no response from the server [default: 3]")
             .long("tcp-operation-retry-count")
             .takes_value(true))
+        .arg(Arg::with_name("output")
+            .help("Output format used by commands supporting structured output [default: text]")
+            .long("output")
+            .short("o")
+            .value_name("FORMAT")
+            .possible_values(&["text", "json"])
+            .takes_value(true))
         .arg(Arg::with_name("verbose")
             .help("Log verbosity. The more -v there are, the more verbose it gets")
             .short("v")
@@ -131,14 +220,181 @@ async fn main() {
                 .long("group-id")
                 .value_name("GROUP_ID")
                 .takes_value(true)
-                .required(true)))
+                .required(true))
+            .arg(Arg::with_name("raw")
+                .help("Displays the persistent subscription as-is from the server")
+                .long("raw")))
         .subcommand(SubCommand::with_name("list-subscriptions")
             .about("List persistent subscriptions")
             .arg(Arg::with_name("raw")
                 .help("Displays the persistent subscriptions as-is from the server")
-                .long("raw")))
-        .subcommand(SubCommand::with_name("create-subscription")
-            .about("Create a persistent subscription")
+                .long("raw"))
+            .arg(Arg::with_name("watch")
+                .help("Keep refreshing a table of the persistent subscriptions, with their lag")
+                .short("w")
+                .long("watch")
+                .conflicts_with("raw"))
+            .arg(Arg::with_name("interval")
+                .help("Delay between two refreshes in watch mode, like 500ms, 2s or 1m [default: 2s]")
+                .long("interval")
+                .takes_value(true)
+                .value_name("DURATION")
+                .requires("watch"))
+            .arg(Arg::with_name("lag-threshold")
+                .help("In watch mode, highlights subscriptions lagging behind by more than that number of events")
+                .long("lag-threshold")
+                .takes_value(true)
+                .value_name("COUNT")
+                .requires("watch"))
+            .arg(Arg::with_name("stream")
+                .help("Only keeps subscriptions whose stream matches that glob pattern, like 'orders-*'")
+                .long("stream")
+                .takes_value(true)
+                .value_name("GLOB"))
+            .arg(Arg::with_name("group")
+                .help("Only keeps subscriptions whose group matches that glob pattern")
+                .long("group")
+                .takes_value(true)
+                .value_name("GLOB"))
+            .arg(Arg::with_name("status")
+                .help("Only keeps subscriptions with that status, like 'Live'")
+                .long("status")
+                .takes_value(true)
+                .value_name("STATUS"))
+            .arg(Arg::with_name("min-lag")
+                .help("Only keeps subscriptions lagging behind by at least that number of events")
+                .long("min-lag")
+                .takes_value(true)
+                .value_name("COUNT"))
+            .arg(Arg::with_name("no-connections")
+                .help("Only keeps subscriptions without any connected client")
+                .long("no-connections"))
+            .arg(Arg::with_name("sort")
+                .help("Sorts subscriptions by lag (descending), processing rate (descending) or name")
+                .long("sort")
+                .takes_value(true)
+                .value_name("FIELD")
+                .possible_values(&["lag", "rate", "name"])))
+        .subcommand(SubCommand::with_name("subscription")
+            .about("Operate a persistent subscription")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("reset")
+                .about("Move a persistent subscription checkpoint, to replay or skip events")
+                .arg(Arg::with_name("stream")
+                    .help("Stream's name")
+                    .long("stream")
+                    .short("s")
+                    .value_name("STREAM_NAME")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("group-id")
+                    .help("Persistent subscription's group id")
+                    .long("group-id")
+                    .short("g")
+                    .value_name("GROUP_ID")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("to")
+                    .help("Event number the group resumes from, 'start' or 'end' of the stream")
+                    .long("to")
+                    .value_name("EVENT_NUMBER")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("strategy")
+                    .help("Writes a new checkpoint or recreates the group with a new starting point. \
+                           'auto' writes a checkpoint when the current one holds an event number")
+                    .long("strategy")
+                    .takes_value(true)
+                    .value_name("STRATEGY")
+                    .possible_values(&["auto", "checkpoint", "recreate"])
+                    .default_value("auto"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the group to restart, like 500ms, 2s or 1m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION"))
+                .arg(Arg::with_name("confirm")
+                    .help("Doesn't ask for confirmation")
+                    .long("confirm")))
+            .subcommand(SubCommand::with_name("position")
+                .about("Decode a persistent subscription checkpoint and compare it with the stream head")
+                .arg(Arg::with_name("stream")
+                    .help("Stream's name")
+                    .long("stream")
+                    .short("s")
+                    .value_name("STREAM_NAME")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("group-id")
+                    .help("Persistent subscription's group id")
+                    .long("group-id")
+                    .short("g")
+                    .value_name("GROUP_ID")
+                    .required(true)
+                    .takes_value(true))
+                .arg(Arg::with_name("stale-after")
+                    .help("Warns when the checkpoint is older than that while clients are connected, like 30s or 5m [default: 5m]")
+                    .long("stale-after")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("bulk")
+                .about("Create, update or delete persistent subscriptions in bulk")
+                .arg(Arg::with_name("action")
+                    .help("Operation applied on every targeted persistent subscription")
+                    .required(true)
+                    .value_name("ACTION")
+                    .possible_values(&["create", "update", "delete"]))
+                .arg(Arg::with_name("stream")
+                    .help("Targets existing subscriptions whose stream matches that glob pattern, like 'orders-*'")
+                    .long("stream")
+                    .takes_value(true)
+                    .value_name("GLOB")
+                    .conflicts_with("file"))
+                .arg(Arg::with_name("group")
+                    .help("Targets existing subscriptions whose group matches that glob pattern")
+                    .long("group")
+                    .takes_value(true)
+                    .value_name("GLOB")
+                    .conflicts_with("file"))
+                .arg(Arg::with_name("file")
+                    .help("CSV file with stream and group columns (plus optional settings columns), or TOML file \
+                           using the compliance [[subscription]] format")
+                    .long("file")
+                    .takes_value(true)
+                    .value_name("PATH"))
+                .args(&subscription_settings_args())
+                .arg(Arg::with_name("confirm")
+                    .help("Doesn't ask for confirmation")
+                    .long("confirm")))
+            .subcommand(SubCommand::with_name("export")
+                .about("Export every persistent subscription definition, using the compliance file format")
+                .arg(Arg::with_name("file")
+                    .help("Writes the definitions to that file instead of the standard output")
+                    .long("file")
+                    .takes_value(true)
+                    .value_name("PATH"))
+                .arg(Arg::with_name("format")
+                    .help("Definitions format [default: guessed from the file extension, toml otherwise]")
+                    .long("format")
+                    .takes_value(true)
+                    .value_name("FORMAT")
+                    .possible_values(&["toml", "json"])))
+            .subcommand(SubCommand::with_name("import")
+                .about("Create the persistent subscriptions defined in an exported file. Existing ones are skipped")
+                .arg(Arg::with_name("file")
+                    .help("File produced by 'subscription export'")
+                    .long("file")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("PATH"))
+                .arg(Arg::with_name("format")
+                    .help("Definitions format [default: guessed from the file extension, toml otherwise]")
+                    .long("format")
+                    .takes_value(true)
+                    .value_name("FORMAT")
+                    .possible_values(&["toml", "json"]))))
+        .subcommand(SubCommand::with_name("consume")
+            .about("Consume a persistent subscription, acking or nacking every delivered event interactively")
             .arg(Arg::with_name("stream")
                 .help("Stream's name")
                 .long("stream")
@@ -153,73 +409,36 @@ async fn main() {
                 .value_name("GROUP_ID")
                 .required(true)
                 .takes_value(true))
-            .arg(Arg::with_name("resolve-link")
-                .help("Determines whether any link events encountered in the stream will be resolved")
-                .long("resolve-link"))
-            .arg(Arg::with_name("start-from")
-                .help("Where the subscription should start from (event number) [default: -1]")
-                .long("start-from")
-                .value_name("EVENT_NUMBER")
-                .takes_value(true))
-            .arg(Arg::with_name("extra-stats")
-                .help("Whether or not in depth latency statistics should be tracked on this subscription")
-                .long("extra-stats"))
-            .arg(Arg::with_name("message-timeout")
-                .help(
-                    "The amount of time, in milliseconds, after which a message \
-                    should be considered to be timeout and retried [default: 30secs]")
-                .value_name("MILLISECONDS")
-                .long("message-timeout")
-                .takes_value(true))
-            .arg(Arg::with_name("max-retry-count")
-                .help(
-                    "The maximum number of retries (due to timeout) before a message \
-                    get considered to be parked [default: 10]")
-                .long("max-retry-count")
+            .arg(Arg::with_name("auto-ack")
+                .help("Acknowledge every event without asking")
+                .long("auto-ack"))
+            .arg(Arg::with_name("max")
+                .help("Stop after that number of delivered events")
+                .long("max")
                 .value_name("COUNT")
                 .takes_value(true))
-            .arg(Arg::with_name("live-buffer-size")
-                .help("The size of the buffer listening to live messages as they happen [default: 500]")
-                .long("live-buffer-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("read-batch-size")
-                .help("The number of events read at a time when paging in history [default: 500]")
-                .long("read-batch-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("history-buffer-size")
-                .help("The number of events read at a time when paging through history [default: 500]")
-                .long("history-buffer-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("checkpoint-after")
-                .help("The amount of time, in milliseconds, to try checkpoint after [default: 2secs]")
-                .long("checkpoint-after")
-                .value_name("MILLISECONDS")
-                .takes_value(true))
-            .arg(Arg::with_name("min-checkpoint-count")
-                .help("The minimum number of messages to checkpoint [default: 10]")
-                .long("min-checkpoint-count")
-                .value_name("COUNT")
-                .takes_value(true))
-            .arg(Arg::with_name("max-checkpoint-count")
-                .help(
-                    "The maximum number of messages to checkpoint. \
-                    If this number is reached , a checkpoint will be forced [default: 1000]")
-                .long("max-checkpoint-count")
+            .arg(Arg::with_name("batch-size")
+                .help("Maximum number of in-flight events for that client [default: 10]")
+                .long("batch-size")
                 .value_name("COUNT")
+                .takes_value(true)))
+        .subcommand(SubCommand::with_name("create-subscription")
+            .about("Create a persistent subscription")
+            .arg(Arg::with_name("stream")
+                .help("Stream's name")
+                .long("stream")
+                .short("s")
+                .value_name("STREAM_NAME")
+                .required(true)
                 .takes_value(true))
-            .arg(Arg::with_name("max-subs-count")
-                .help("The maximum number of subscribers allowed [default: 0 (means no limit)]")
-                .long("max-subs-count")
-                .value_name("COUNT")
+            .arg(Arg::with_name("group-id")
+                .help("Persistent subscription's group id")
+                .long("group-id")
+                .short("g")
+                .value_name("GROUP_ID")
+                .required(true)
                 .takes_value(true))
-            .arg(Arg::with_name("consumer-strategy")
-                .help("The strategy to use for distributing events to client consumers [default: RoundRobin]")
-                .long("consumer-strategy")
-                .value_name("STRATEGY")
-                .takes_value(true)))
+            .args(&subscription_settings_args()))
         .subcommand(SubCommand::with_name("update-subscription")
             .about("Update a persistent subscription")
             .arg(Arg::with_name("stream")
@@ -236,73 +455,7 @@ async fn main() {
                 .value_name("GROUP_ID")
                 .required(true)
                 .takes_value(true))
-            .arg(Arg::with_name("resolve-link")
-                .help("Determines whether any link events encountered in the stream will be resolved")
-                .long("resolve-link"))
-            .arg(Arg::with_name("start-from")
-                .help("Where the subscription should start from (event number) [default: -1]")
-                .long("start-from")
-                .value_name("EVENT_NUMBER")
-                .takes_value(true))
-            .arg(Arg::with_name("extra-stats")
-                .help("Whether or not in depth latency statistics should be tracked on this subscription")
-                .long("extra-stats"))
-            .arg(Arg::with_name("message-timeout")
-                .help(
-                    "The amount of time, in milliseconds, after which a message \
-                    should be considered to be timeout and retried [default: 30secs]")
-                .value_name("MILLISECONDS")
-                .long("message-timeout")
-                .takes_value(true))
-            .arg(Arg::with_name("max-retry-count")
-                .help(
-                    "The maximum number of retries (due to timeout) before a message \
-                    get considered to be parked [default: 10]")
-                .long("max-retry-count")
-                .value_name("COUNT")
-                .takes_value(true))
-            .arg(Arg::with_name("live-buffer-size")
-                .help("The size of the buffer listening to live messages as they happen [default: 500]")
-                .long("live-buffer-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("read-batch-size")
-                .help("The number of events read at a time when paging in history [default: 500]")
-                .long("read-batch-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("history-buffer-size")
-                .help("The number of events read at a time when paging through history [default: 500]")
-                .long("history-buffer-size")
-                .value_name("BUFFER_SIZE")
-                .takes_value(true))
-            .arg(Arg::with_name("checkpoint-after")
-                .help("The amount of time, in milliseconds, to try checkpoint after [default: 2secs]")
-                .long("checkpoint-after")
-                .value_name("MILLISECONDS")
-                .takes_value(true))
-            .arg(Arg::with_name("min-checkpoint-count")
-                .help("The minimum number of messages to checkpoint [default: 10]")
-                .long("min-checkpoint-count")
-                .value_name("COUNT")
-                .takes_value(true))
-            .arg(Arg::with_name("max-checkpoint-count")
-                .help(
-                    "The maximum number of messages to checkpoint. \
-                    If this number is reached , a checkpoint will be forced [default: 1000]")
-                .long("max-checkpoint-count")
-                .value_name("COUNT")
-                .takes_value(true))
-            .arg(Arg::with_name("max-subs-count")
-                .help("The maximum number of subscribers allowed [default: 0 (means no limit)]")
-                .long("max-subs-count")
-                .value_name("COUNT")
-                .takes_value(true))
-            .arg(Arg::with_name("consumer-strategy")
-                .help("The strategy to use for distributing events to client consumers [default: RoundRobin]")
-                .long("consumer-strategy")
-                .value_name("STRATEGY")
-                .takes_value(true)))
+            .args(&subscription_settings_args()))
         .subcommand(SubCommand::with_name("delete-subscription")
             .about("Delete a persistent subscription")
             .arg(Arg::with_name("stream")
@@ -360,9 +513,255 @@ async fn main() {
                 .value_name("KIND")
                 .short("k")
                 .long("kind")
-                .takes_value(true)))
+                .takes_value(true))
+            .arg(Arg::with_name("detailed")
+                .help("Show progress, position, checkpoint and pending counts of each projection")
+                .long("detailed"))
+            .arg(Arg::with_name("status")
+                .help("Only keep projections with that status, like Running, Stopped or Faulted")
+                .long("status")
+                .takes_value(true)
+                .value_name("STATUS"))
+            .arg(Arg::with_name("mode")
+                .help("Only keep projections with that mode, like Continuous, OneTime or Transient")
+                .long("mode")
+                .takes_value(true)
+                .value_name("MODE")))
+        .subcommand(SubCommand::with_name("projection")
+            .about("Operate existing projections")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("enable")
+                .about("Enable a projection and wait until it runs")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the projection to change status, like 500ms, 30s or 2m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("disable")
+                .about("Disable a projection and wait until it stops")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the projection to change status, like 500ms, 30s or 2m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("abort")
+                .about("Abort a projection without writing a checkpoint and wait until it stops")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the projection to change status, like 500ms, 30s or 2m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("reset")
+                .about("Reset a projection and wait until it settles")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the projection to change status, like 500ms, 30s or 2m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("delete")
+                .about("Delete a projection, disabling it first if it is running")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("timeout")
+                    .help("How long to wait for the projection to change status, like 500ms, 30s or 2m [default: 30s]")
+                    .long("timeout")
+                    .takes_value(true)
+                    .value_name("DURATION"))
+                .arg(Arg::with_name("delete-checkpoint-stream")
+                    .help("Also delete the projection's checkpoint stream")
+                    .long("delete-checkpoint-stream"))
+                .arg(Arg::with_name("delete-state-stream")
+                    .help("Also delete the projection's state stream")
+                    .long("delete-state-stream"))
+                .arg(Arg::with_name("delete-emitted-streams")
+                    .help("Also delete the streams the projection emitted to (requires --track-emitted-streams at creation)")
+                    .long("delete-emitted-streams"))
+                .arg(Arg::with_name("confirm")
+                    .long("confirm")
+                    .required(true)))
+            .subcommand(SubCommand::with_name("state")
+                .about("Show a projection's state")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("partition")
+                    .help("Partition's name, for projections using partitionBy or foreachStream")
+                    .short("p")
+                    .long("partition")
+                    .takes_value(true)
+                    .value_name("PARTITION"))
+                .arg(Arg::with_name("watch")
+                    .help("Keep fetching the state and show what changed between fetches")
+                    .short("w")
+                    .long("watch"))
+                .arg(Arg::with_name("interval")
+                    .help("Delay between two fetches in watch mode, like 500ms, 2s or 1m [default: 2s]")
+                    .long("interval")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("result")
+                .about("Show a projection's result")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("partition")
+                    .help("Partition's name, for projections using partitionBy or foreachStream")
+                    .short("p")
+                    .long("partition")
+                    .takes_value(true)
+                    .value_name("PARTITION"))
+                .arg(Arg::with_name("watch")
+                    .help("Keep fetching the result and show what changed between fetches")
+                    .short("w")
+                    .long("watch"))
+                .arg(Arg::with_name("interval")
+                    .help("Delay between two fetches in watch mode, like 500ms, 2s or 1m [default: 2s]")
+                    .long("interval")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("stats")
+                .about("Show detailed statistics of projections")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name, shows every projection when omitted")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("status")
+                    .help("Only keep projections with that status, like Running, Stopped or Faulted")
+                    .long("status")
+                    .takes_value(true)
+                    .value_name("STATUS"))
+                .arg(Arg::with_name("mode")
+                    .help("Only keep projections with that mode, like Continuous, OneTime or Transient")
+                    .long("mode")
+                    .takes_value(true)
+                    .value_name("MODE")))
+            .subcommand(SubCommand::with_name("test")
+                .about("Run a projection script locally against events read from a JSON lines file")
+                .arg(Arg::with_name("script")
+                    .help("Path to the projection's Javascript script")
+                    .long("script")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("FILEPATH"))
+                .arg(Arg::with_name("events")
+                    .help("Path to a file with one JSON event per line: {\"stream\", \"type\", \"data\", \"metadata\", \"number\", \"id\"}")
+                    .long("events")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("FILEPATH")))
+            .subcommand(SubCommand::with_name("dev")
+                .about("Deploy a projection script on every save, then reset, restart and follow the projection")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name, a continuous projection is created if it doesn't exist")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("script")
+                    .help("Path to the projection's Javascript script")
+                    .long("script")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("FILEPATH"))
+                .arg(Arg::with_name("emit")
+                    .help("Enable the ability for the projection to write to streams")
+                    .long("emit"))
+                .arg(Arg::with_name("track-emitted-streams")
+                    .help("Write the name of the streams the projection is managing to a separate stream")
+                    .long("track-emitted-streams"))
+                .arg(Arg::with_name("interval")
+                    .help("Delay between two checks of the script and the projection, like 500ms or 2s [default: 500ms]")
+                    .long("interval")
+                    .takes_value(true)
+                    .value_name("DURATION")))
+            .subcommand(SubCommand::with_name("emitted-streams")
+                .about("List the streams a projection emitted to, with their event counts. Requires --track-emitted-streams at creation")
+                .arg(Arg::with_name("name")
+                    .help("Projection's name")
+                    .short("n")
+                    .long("name")
+                    .takes_value(true)
+                    .required(true)
+                    .value_name("NAME"))
+                .arg(Arg::with_name("delete")
+                    .help("Delete the emitted streams. The projection must not be running")
+                    .long("delete"))
+                .arg(Arg::with_name("confirm")
+                    .help("Don't ask for confirmation before deleting")
+                    .long("confirm")
+                    .requires("delete"))))
+        .subcommand(SubCommand::with_name("query")
+            .about("Run an ad-hoc query through a transient projection, print its result and delete it")
+            .arg(Arg::with_name("script")
+                .help("Path to the query's Javascript script")
+                .long("script")
+                .takes_value(true)
+                .value_name("FILEPATH")
+                .required_unless("eval")
+                .conflicts_with("eval"))
+            .arg(Arg::with_name("eval")
+                .help("Inline query's Javascript script")
+                .short("e")
+                .long("eval")
+                .takes_value(true)
+                .value_name("SCRIPT"))
+            .arg(Arg::with_name("partition")
+                .help("Partition's name, for queries using partitionBy or foreachStream")
+                .short("p")
+                .long("partition")
+                .takes_value(true)
+                .value_name("PARTITION"))
+            .arg(Arg::with_name("timeout")
+                .help("How long to wait for the query to complete, like 500ms, 30s or 2m [default: 30s]")
+                .long("timeout")
+                .takes_value(true)
+                .value_name("DURATION")))
         .subcommand(SubCommand::with_name("export")
-            .about("Export events from a database to another, that command connects to source database through its TCP port")
+            .about("Export events from a database to another or to JSON Lines files, that command connects to source database through its TCP port")
             .arg(Arg::with_name("from-stream")
                 .help("Stream to copy events from")
                 .long("from-stream")
@@ -383,12 +782,53 @@ async fn main() {
                 .long("to-host")
                 .value_name("HOST")
                 .takes_value(true)
-                .required(true))
+                .required_unless_one(&["to-file", "to-dir"])
+                .conflicts_with_all(&["to-file", "to-dir"]))
             .arg(Arg::with_name("to-tcp-port")
                 .help("TCP port of the destination database [default: 1113]")
                 .long("to-tcp-port")
                 .value_name("PORT")
                 .takes_value(true))
+            .arg(Arg::with_name("to-file")
+                .help("Writes events to a JSON Lines file, compressed when its name ends with .gz or .zst")
+                .long("to-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with("to-dir"))
+            .arg(Arg::with_name("to-dir")
+                .help("Writes events to a directory, with a JSON Lines file per stream")
+                .long("to-dir")
+                .value_name("DIRECTORY")
+                .takes_value(true))
+            .arg(Arg::with_name("compress")
+                .help("Compression of the written files [default: guessed from --to-file extension, none otherwise]")
+                .long("compress")
+                .value_name("ALGORITHM")
+                .takes_value(true)
+                .possible_values(&["none", "gzip", "zstd"]))
+            .arg(Arg::with_name("concurrency")
+                .help("Number of streams copied at the same time when exporting a category [default: 1]")
+                .long("concurrency")
+                .value_name("COUNT")
+                .takes_value(true))
+            .arg(Arg::with_name("batch-size")
+                .help("Number of events read and written at once [default: 500]")
+                .long("batch-size")
+                .value_name("COUNT")
+                .takes_value(true))
+            .arg(Arg::with_name("verify")
+                .help("Once copied, compares event ids, types and payloads of every exported stream between the source and the destination")
+                .long("verify"))
+            .arg(Arg::with_name("verify-only")
+                .help("Only compares the source and the destination, without copying anything")
+                .long("verify-only")
+                .conflicts_with_all(&["verify", "checkpoint-file"]))
+            .arg(Arg::with_name("checkpoint-file")
+                .help("Records the export progress in that file and resumes from it when it exists")
+                .long("checkpoint-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .conflicts_with_all(&["recent", "top"]))
             .arg(Arg::with_name("recent")
                 .help("Takes only the latest 50 entries")
                 .long("recent"))
@@ -396,6 +836,224 @@ async fn main() {
                 .help("Takes only the latest N entries")
                 .long("top")
                 .takes_value(true)))
+        .subcommand(SubCommand::with_name("import")
+            .about("Import events from a file written by 'export --to-file', keeping their ids")
+            .arg(Arg::with_name("from-file")
+                .help("JSON Lines archive, possibly gzip or zstd compressed")
+                .long("from-file")
+                .value_name("PATH")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("map")
+                .help("Renames a stream while importing, like 'orders=orders-v2' or by prefix 'orders-*=old-orders-*'")
+                .long("map")
+                .value_name("FROM=TO")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1))
+            .arg(Arg::with_name("expected-version")
+                .help("Checks done on the destination streams: none, streams must not exist yet, or events must \
+                       keep their archived number")
+                .long("expected-version")
+                .value_name("CHECK")
+                .takes_value(true)
+                .possible_values(&["any", "no-stream", "archive"])
+                .default_value("any")))
+        .subcommand(SubCommand::with_name("copy-events")
+            .about("Copy a range of events from a stream to another stream of the same database")
+            .arg(Arg::with_name("from-stream")
+                .help("Stream to copy events from")
+                .long("from-stream")
+                .value_name("STREAM_NAME")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("to-stream")
+                .help("Stream to copy events to")
+                .long("to-stream")
+                .value_name("STREAM_NAME")
+                .takes_value(true)
+                .required(true))
+            .arg(Arg::with_name("from")
+                .help("Event number of the first event to copy [default: 0]")
+                .long("from")
+                .value_name("EVENT_NUMBER")
+                .takes_value(true))
+            .arg(Arg::with_name("to")
+                .help("Event number of the last event to copy, inclusive [default: end of stream]")
+                .long("to")
+                .value_name("EVENT_NUMBER")
+                .takes_value(true))
+            .arg(Arg::with_name("as-links")
+                .help("Write link events ($>) pointing to the source events instead of full copies")
+                .long("as-links"))
+            .arg(Arg::with_name("regenerate-ids")
+                .help("Give the copied events new ids instead of preserving the source ones")
+                .long("regenerate-ids")
+                .conflicts_with("as-links")))
+        .subcommand(SubCommand::with_name("user")
+            .about("Manage database users")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("list")
+                .about("List all users"))
+            .subcommand(SubCommand::with_name("create")
+                .about("Create a user. The password is asked through a prompt")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true))
+                .arg(Arg::with_name("full-name")
+                    .help("User's full name [default: LOGIN]")
+                    .long("full-name")
+                    .value_name("NAME")
+                    .takes_value(true))
+                .arg(Arg::with_name("group")
+                    .help("Group the user belongs to. Can be used multiple times")
+                    .long("group")
+                    .value_name("GROUP")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)))
+            .subcommand(SubCommand::with_name("update")
+                .about("Update a user's full name or group membership")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true))
+                .arg(Arg::with_name("full-name")
+                    .help("User's new full name")
+                    .long("full-name")
+                    .value_name("NAME")
+                    .takes_value(true))
+                .arg(Arg::with_name("groups")
+                    .help("Comma-separated list of groups replacing the current membership")
+                    .long("groups")
+                    .value_name("GROUPS")
+                    .takes_value(true))
+                .arg(Arg::with_name("add-group")
+                    .help("Adds the user to a group. Can be used multiple times")
+                    .long("add-group")
+                    .value_name("GROUP")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1))
+                .arg(Arg::with_name("remove-group")
+                    .help("Removes the user from a group. Can be used multiple times")
+                    .long("remove-group")
+                    .value_name("GROUP")
+                    .takes_value(true)
+                    .multiple(true)
+                    .number_of_values(1)))
+            .subcommand(SubCommand::with_name("enable")
+                .about("Enable a user")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true)))
+            .subcommand(SubCommand::with_name("disable")
+                .about("Disable a user")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true)))
+            .subcommand(SubCommand::with_name("delete")
+                .about("Delete a user")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true))
+                .arg(Arg::with_name("confirm")
+                    .help("Doesn't ask for confirmation")
+                    .long("confirm")))
+            .subcommand(SubCommand::with_name("reset-password")
+                .about("Reset a user's password. The new password is asked through a prompt")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true)))
+            .subcommand(SubCommand::with_name("change-password")
+                .about(
+                    "Change a user's password. Current and new passwords are asked \
+                    through a prompt")
+                .arg(Arg::with_name("LOGIN")
+                    .help("User's login")
+                    .required(true))))
+        .subcommand(SubCommand::with_name("acl")
+            .about("Inspect and edit stream access control lists")
+            .setting(AppSettings::SubcommandRequiredElseHelp)
+            .subcommand(SubCommand::with_name("show")
+                .about("Show the effective ACL of a stream, combining the stream ACL and the default ACLs")
+                .arg(Arg::with_name("stream")
+                    .help("A stream's name")
+                    .short("s")
+                    .long("stream")
+                    .value_name("STREAM_NAME")
+                    .takes_value(true)
+                    .required(true)))
+            .subcommand(SubCommand::with_name("set")
+                .about("Update a stream ACL. Roles that are not passed keep their current value")
+                .arg(Arg::with_name("stream")
+                    .help("A stream's name")
+                    .short("s")
+                    .long("stream")
+                    .value_name("STREAM_NAME")
+                    .takes_value(true)
+                    .required(true))
+                .arg(Arg::with_name("read")
+                    .help("Comma-separated roles allowed to read the stream")
+                    .long("read")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("write")
+                    .help("Comma-separated roles allowed to write to the stream")
+                    .long("write")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("delete")
+                    .help("Comma-separated roles allowed to delete the stream")
+                    .long("delete")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("meta-read")
+                    .help("Comma-separated roles allowed to read the stream's metadata")
+                    .long("meta-read")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("meta-write")
+                    .help("Comma-separated roles allowed to write the stream's metadata")
+                    .long("meta-write")
+                    .value_name("ROLES")
+                    .takes_value(true)))
+            .subcommand(SubCommand::with_name("show-default")
+                .about("Show the user and system streams default ACLs stored in $settings"))
+            .subcommand(SubCommand::with_name("set-default")
+                .about("Update the user or system streams default ACL stored in $settings. Roles that are not passed keep their current value")
+                .arg(Arg::with_name("user")
+                    .help("Targets the default ACL of user streams")
+                    .long("user")
+                    .conflicts_with("system")
+                    .required_unless("system"))
+                .arg(Arg::with_name("system")
+                    .help("Targets the default ACL of system streams (starting with $)")
+                    .long("system"))
+                .arg(Arg::with_name("read")
+                    .help("Comma-separated roles allowed to read the stream")
+                    .long("read")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("write")
+                    .help("Comma-separated roles allowed to write to the stream")
+                    .long("write")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("delete")
+                    .help("Comma-separated roles allowed to delete the stream")
+                    .long("delete")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("meta-read")
+                    .help("Comma-separated roles allowed to read the stream's metadata")
+                    .long("meta-read")
+                    .value_name("ROLES")
+                    .takes_value(true))
+                .arg(Arg::with_name("meta-write")
+                    .help("Comma-separated roles allowed to write the stream's metadata")
+                    .long("meta-write")
+                    .value_name("ROLES")
+                    .takes_value(true))))
         .subcommand(SubCommand::with_name("backup")
             .about("Backup an eventstore node's data")
             .arg(Arg::with_name("source-directory")
@@ -453,18 +1111,115 @@ async fn main() {
             command::list::subscriptions::run(&matches, params, api).await
         } else if let Some(params) = matches.subcommand_matches("list-subscription") {
             command::list::subscription::run(&matches, params, api).await
+        } else if let Some(params) = matches.subcommand_matches("subscription") {
+            match params.subcommand() {
+                ("reset", Some(params)) => {
+                    command::subscription::reset::run(&matches, params, api).await
+                }
+
+                ("position", Some(params)) => {
+                    command::subscription::position::run(&matches, params, api).await
+                }
+
+                ("bulk", Some(params)) => {
+                    command::subscription::bulk::run(&matches, params, api).await
+                }
+
+                ("export", Some(params)) => {
+                    command::subscription::export::run(&matches, params, api).await
+                }
+
+                ("import", Some(params)) => {
+                    command::subscription::import::run(&matches, params, api).await
+                }
+
+                _ => Ok(()),
+            }
+        } else if let Some(params) = matches.subcommand_matches("consume") {
+            command::consume::run(&matches, params).await
         } else if let Some(params) = matches.subcommand_matches("create-subscription") {
             command::create::subscription::run(&matches, params, user_opt).await
         } else if let Some(params) = matches.subcommand_matches("update-subscription") {
-            command::update::subscription::run(&matches, params, user_opt).await
+            command::update::subscription::run(&matches, params, api).await
         } else if let Some(params) = matches.subcommand_matches("delete-subscription") {
             command::delete::subscription::run(&matches, params, user_opt).await
         } else if let Some(params) = matches.subcommand_matches("create-projection") {
             command::create::projection::run(&matches, params, api).await
         } else if let Some(params) = matches.subcommand_matches("list-projections") {
             command::list::projections::run(&matches, params, api).await
+        } else if let Some(params) = matches.subcommand_matches("projection") {
+            match params.subcommand() {
+                ("enable", Some(params)) => {
+                    command::projection::enable::run(&matches, params, api).await
+                }
+                ("disable", Some(params)) => {
+                    command::projection::disable::run(&matches, params, api).await
+                }
+                ("abort", Some(params)) => {
+                    command::projection::abort::run(&matches, params, api).await
+                }
+                ("reset", Some(params)) => {
+                    command::projection::reset::run(&matches, params, api).await
+                }
+                ("delete", Some(params)) => {
+                    command::projection::delete::run(&matches, params, api).await
+                }
+                ("state", Some(params)) => {
+                    command::projection::state::run(&matches, params, api).await
+                }
+                ("result", Some(params)) => {
+                    command::projection::result::run(&matches, params, api).await
+                }
+                ("stats", Some(params)) => {
+                    command::projection::stats::run(&matches, params, api).await
+                }
+                ("test", Some(params)) => command::projection::test::run(&matches, params).await,
+                ("dev", Some(params)) => command::projection::dev::run(&matches, params, api).await,
+                ("emitted-streams", Some(params)) => {
+                    command::projection::emitted::run(&matches, params, api).await
+                }
+                _ => Ok(()),
+            }
+        } else if let Some(params) = matches.subcommand_matches("query") {
+            command::query::run(&matches, params, api).await
         } else if let Some(params) = matches.subcommand_matches("export") {
             command::export::run(&matches, params).await
+        } else if let Some(params) = matches.subcommand_matches("import") {
+            command::import::run(&matches, params).await
+        } else if let Some(params) = matches.subcommand_matches("copy-events") {
+            command::copy::events::run(&matches, params).await
+        } else if let Some(params) = matches.subcommand_matches("user") {
+            match params.subcommand() {
+                ("list", Some(params)) => command::user::list::run(&matches, params, api).await,
+                ("create", Some(params)) => command::user::create::run(&matches, params, api).await,
+                ("update", Some(params)) => command::user::update::run(&matches, params, api).await,
+                ("enable", Some(params)) => {
+                    command::user::enable::run(&matches, params, api, true).await
+                }
+                ("disable", Some(params)) => {
+                    command::user::enable::run(&matches, params, api, false).await
+                }
+                ("delete", Some(params)) => command::user::delete::run(&matches, params, api).await,
+                ("reset-password", Some(params)) => {
+                    command::user::password::reset(&matches, params, api).await
+                }
+                ("change-password", Some(params)) => {
+                    command::user::password::change(&matches, params, api).await
+                }
+                _ => Ok(()),
+            }
+        } else if let Some(params) = matches.subcommand_matches("acl") {
+            match params.subcommand() {
+                ("show", Some(params)) => command::acl::show::run(&matches, params).await,
+                ("set", Some(params)) => command::acl::set::run(&matches, params).await,
+                ("show-default", Some(params)) => {
+                    command::acl::defaults::show(&matches, params).await
+                }
+                ("set-default", Some(params)) => {
+                    command::acl::defaults::set(&matches, params).await
+                }
+                _ => Ok(()),
+            }
         } else if let Some(params) = matches.subcommand_matches("backup") {
             command::backup::run(&matches, params)
         } else if let Some(params) = matches.subcommand_matches("apply-compliance") {